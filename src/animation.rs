@@ -0,0 +1,728 @@
+use std::ops::RangeInclusive;
+
+use serde::Deserialize;
+
+use crate::config::{self, Config, ConfigError, Light, MotionKey, Object, Projection, Transform};
+
+const DEFAULT_FPS: f32 = 24.0;
+/// Motion keys per frame for objects with animated transforms
+const MOTION_KEYS: usize = 4;
+
+/// How a track moves from one keyframe to the next.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Hold the value until the next keyframe
+    Step,
+    Linear,
+    /// A smooth curve through the keyframes that eases in and out of the
+    /// first and last one
+    Bezier,
+}
+
+/// The value of a track at a keyframe, a number for things like the field of
+/// view and `[x, y, z]` for positions and colours.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Value {
+    Scalar(f32),
+    Vector([f32; 3]),
+}
+
+impl Value {
+    fn components(self) -> [f32; 3] {
+        match self {
+            Value::Scalar(value) => [value, 0.0, 0.0],
+            Value::Vector(value) => value,
+        }
+    }
+
+    /// Sum of `terms` with their weights, of the same kind as the first term
+    fn weighted_sum(terms: &[(Value, f32)]) -> Value {
+        let mut sum = [0.0; 3];
+        for (value, weight) in terms {
+            for (total, component) in sum.iter_mut().zip(value.components()) {
+                *total += component * weight;
+            }
+        }
+
+        match terms[0].0 {
+            Value::Scalar(_) => Value::Scalar(sum[0]),
+            Value::Vector(_) => Value::Vector(sum),
+        }
+    }
+
+    fn scalar(self, target: &Target) -> Result<f32, ConfigError> {
+        match self {
+            Value::Scalar(value) => Ok(value),
+            Value::Vector(_) => Err(animation_error(format!(
+                "Animation track for {:?} needs numbers as values",
+                target
+            ))),
+        }
+    }
+
+    fn vector(self, target: &Target) -> Result<[f32; 3], ConfigError> {
+        match self {
+            Value::Vector(value) => Ok(value),
+            Value::Scalar(_) => Err(animation_error(format!(
+                "Animation track for {:?} needs [x, y, z] values",
+                target
+            ))),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CameraProperty {
+    Position,
+    LookAt,
+    Up,
+    /// Only for perspective and fisheye cameras
+    Fov,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LightProperty {
+    /// Only for point, spot and area lights
+    Origin,
+    /// Only for directional, spot and area lights
+    Direction,
+    Color,
+    Intensity,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MaterialProperty {
+    AmbientColor,
+    DiffuseColor,
+    SpecularColor,
+    EmissionColor,
+}
+
+/// The property of the config a track animates. Scenes and cameras are
+/// selected by name and default to the first one, objects and lights by their
+/// index in the scene.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum Target {
+    Camera {
+        camera: Option<String>,
+        property: CameraProperty,
+    },
+    /// The value of the object's `transform`th transform
+    Object {
+        scene: Option<String>,
+        object: usize,
+        transform: usize,
+    },
+    Light {
+        scene: Option<String>,
+        light: usize,
+        property: LightProperty,
+    },
+    Material {
+        material: String,
+        property: MaterialProperty,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Keyframe {
+    frame: f32,
+    value: Value,
+    interpolation: Interpolation,
+}
+
+#[derive(Debug, Clone)]
+pub struct Track {
+    target: Target,
+    /// Sorted by frame
+    keyframes: Vec<Keyframe>,
+}
+
+impl Track {
+    /// The value at `frame`, which may lie between frames. The first and last
+    /// keyframes hold before and after the animated range.
+    pub fn value_at(&self, frame: f32) -> Option<Value> {
+        let keyframes = &self.keyframes;
+        let next = keyframes.iter().position(|keyframe| keyframe.frame > frame);
+        let index = match next {
+            None => return keyframes.last().map(|keyframe| keyframe.value),
+            Some(0) => return Some(keyframes[0].value),
+            Some(next) => next - 1,
+        };
+
+        let (from, to) = (&keyframes[index], &keyframes[index + 1]);
+        let span = to.frame - from.frame;
+        let t = (frame - from.frame) / span;
+
+        Some(match from.interpolation {
+            Interpolation::Step => from.value,
+            Interpolation::Linear => Value::weighted_sum(&[(from.value, 1.0 - t), (to.value, t)]),
+            Interpolation::Bezier => {
+                // Control points a third of the way along the tangents, which
+                // makes the curve's progress in time linear
+                let control = |keyframe: usize, sign: f32| {
+                    let mut terms = vec![(keyframes[keyframe].value, 1.0)];
+                    terms.extend(self.tangent(keyframe, sign * span / 3.0));
+                    Value::weighted_sum(&terms)
+                };
+                let s = 1.0 - t;
+
+                Value::weighted_sum(&[
+                    (from.value, s * s * s),
+                    (control(index, 1.0), 3.0 * s * s * t),
+                    (control(index + 1, -1.0), 3.0 * s * t * t),
+                    (to.value, t * t * t),
+                ])
+            }
+        })
+    }
+
+    /// The slope at the keyframe at `index` scaled by `scale`, as terms of a
+    /// weighted sum. Catmull-Rom style from the neighbouring keyframes, flat at
+    /// the ends.
+    fn tangent(&self, index: usize, scale: f32) -> Vec<(Value, f32)> {
+        let keyframes = &self.keyframes;
+        if index == 0 || index + 1 == keyframes.len() {
+            return vec![];
+        }
+
+        let (previous, next) = (&keyframes[index - 1], &keyframes[index + 1]);
+        let weight = scale / (next.frame - previous.frame);
+
+        vec![(next.value, weight), (previous.value, -weight)]
+    }
+
+    fn apply(&self, config: &mut Config, value: Value) -> Result<(), ConfigError> {
+        let target = &self.target;
+
+        match target {
+            Target::Camera { camera, property } => {
+                let (index, _) = config.camera(camera.as_deref())?;
+                let camera = &mut config.cameras[index];
+
+                match property {
+                    CameraProperty::Position => camera.position = value.vector(target)?,
+                    CameraProperty::LookAt => camera.look_at = value.vector(target)?,
+                    CameraProperty::Up => camera.up = value.vector(target)?,
+                    CameraProperty::Fov => match &mut camera.projection {
+                        Projection::Perspective { fov, .. } | Projection::Fisheye { fov, .. } => {
+                            *fov = value.scalar(target)?
+                        }
+                        _ => {
+                            return Err(animation_error(format!(
+                                "Animation track for {:?} needs a camera with a field of view",
+                                target
+                            )))
+                        }
+                    },
+                }
+            }
+            Target::Object {
+                scene,
+                object,
+                transform,
+            } => {
+                let (transforms, _) = object_mut(config, scene.as_deref(), *object)?;
+                set_transform(transforms, *transform, value, target)?;
+            }
+            Target::Light {
+                scene,
+                light,
+                property,
+            } => {
+                let (index, _) = config.scene(scene.as_deref())?;
+                let Some(light) = config.scenes[index].lights.get_mut(*light) else {
+                    return Err(animation_error(format!(
+                        "Animation track targets light {} which doesn't exist",
+                        light
+                    )));
+                };
+
+                match (light, property) {
+                    (
+                        Light::PointLight { origin, .. }
+                        | Light::SpotLight { origin, .. }
+                        | Light::AreaLight { origin, .. },
+                        LightProperty::Origin,
+                    ) => *origin = value.vector(target)?,
+                    (
+                        Light::DirectionalLight { direction, .. }
+                        | Light::SpotLight { direction, .. },
+                        LightProperty::Direction,
+                    ) => *direction = value.vector(target)?,
+                    (Light::AreaLight { direction, .. }, LightProperty::Direction) => {
+                        *direction = Some(value.vector(target)?)
+                    }
+                    (
+                        Light::PointLight { color, .. }
+                        | Light::DirectionalLight { color, .. }
+                        | Light::SpotLight { color, .. }
+                        | Light::AreaLight { color, .. },
+                        LightProperty::Color,
+                    ) => *color = value.vector(target)?,
+                    (
+                        Light::PointLight { intensity, .. }
+                        | Light::DirectionalLight { intensity, .. }
+                        | Light::SpotLight { intensity, .. }
+                        | Light::AreaLight { intensity, .. },
+                        LightProperty::Intensity,
+                    ) => *intensity = value.scalar(target)?,
+                    _ => {
+                        return Err(animation_error(format!(
+                            "Animation track for {:?} targets a light without that property",
+                            target
+                        )))
+                    }
+                }
+            }
+            Target::Material { material, property } => {
+                let Some(material) = config
+                    .materials
+                    .iter_mut()
+                    .find(|candidate| &candidate.name == material)
+                else {
+                    return Err(animation_error(format!(
+                        "Animation track targets material {} which doesn't exist",
+                        material
+                    )));
+                };
+
+                let color = value.vector(target)?;
+                match property {
+                    MaterialProperty::AmbientColor => material.ambient_color = color,
+                    MaterialProperty::DiffuseColor => material.diffuse_color = color,
+                    MaterialProperty::SpecularColor => material.specular_color = color,
+                    MaterialProperty::EmissionColor => material.emission_color = Some(color),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&config::Track> for Track {
+    fn from(config: &config::Track) -> Self {
+        let mut keyframes: Vec<_> = config
+            .keyframes
+            .iter()
+            .map(|keyframe| Keyframe {
+                frame: keyframe.frame,
+                value: keyframe.value,
+                interpolation: keyframe.interpolation.unwrap_or(Interpolation::Linear),
+            })
+            .collect();
+        keyframes.sort_by(|a, b| a.frame.total_cmp(&b.frame));
+
+        Self {
+            target: config.target.clone(),
+            keyframes,
+        }
+    }
+}
+
+/// Keyframed changes to the cameras, objects, lights and materials of a config
+/// over a range of frames.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    pub frames: RangeInclusive<u32>,
+    /// Frames per second, turns the shutter speed of physical cameras into
+    /// the part of a frame their shutter is open
+    pub fps: f32,
+    tracks: Vec<Track>,
+}
+
+impl Animation {
+    /// The animation of `config`, if it has one. Objects with animated
+    /// transforms get their motion from the animation, explicit `motion` on
+    /// them would be overwritten and is an error.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, ConfigError> {
+        let Some(animation) = &config.animation else {
+            return Ok(None);
+        };
+
+        for track in &animation.tracks {
+            let Target::Object { scene, object, .. } = &track.target else {
+                continue;
+            };
+            let (_, scene) = config.scene(scene.as_deref())?;
+            if let Some(
+                Object::Sphere {
+                    motion: Some(_), ..
+                }
+                | Object::Plane {
+                    motion: Some(_), ..
+                }
+                | Object::Mesh {
+                    motion: Some(_), ..
+                }
+                | Object::MeshInstance {
+                    motion: Some(_), ..
+                },
+            ) = scene.objects.get(*object)
+            {
+                return Err(animation_error(format!(
+                    "Animation track for {:?} targets an object with motion keys, remove either",
+                    track.target
+                )));
+            }
+        }
+
+        Ok(Some(Self::from(animation)))
+    }
+
+    /// Set every animated property of `config` to its value at `frame`
+    pub fn apply(&self, config: &mut Config, frame: f32) -> Result<(), ConfigError> {
+        for track in &self.tracks {
+            if let Some(value) = track.value_at(frame) {
+                track.apply(config, value)?;
+            }
+        }
+
+        // Physical cameras without a shutter interval keep the shutter open for
+        // their shutter speed
+        for camera in &mut config.cameras {
+            if let (Projection::Physical(physical), None) = (&camera.projection, camera.shutter) {
+                if let Some(shutter_speed) = physical.shutter_speed {
+                    camera.shutter = Some(config::Shutter {
+                        open: 0.0,
+                        close: (shutter_speed * self.fps).min(1.0),
+                    });
+                }
+            }
+        }
+
+        // Objects with animated transforms keep moving until the next frame,
+        // which blurs them while the camera's shutter is open
+        let mut moving = vec![];
+        for track in &self.tracks {
+            if let Target::Object { scene, object, .. } = &track.target {
+                if !moving.contains(&(scene.as_deref(), *object)) {
+                    moving.push((scene.as_deref(), *object));
+                }
+            }
+        }
+
+        for (scene, object) in moving {
+            let (transforms, _) = object_mut(config, scene, object)?;
+            let current = transforms.clone();
+            let mut keys = vec![];
+
+            for key in 0..=MOTION_KEYS {
+                let time = key as f32 / MOTION_KEYS as f32;
+                let mut transforms = current.clone();
+                for track in &self.tracks {
+                    let Target::Object {
+                        scene: track_scene,
+                        object: track_object,
+                        transform,
+                    } = &track.target
+                    else {
+                        continue;
+                    };
+                    if (track_scene.as_deref(), *track_object) != (scene, object) {
+                        continue;
+                    }
+
+                    if let Some(value) = track.value_at(frame + time) {
+                        set_transform(&mut transforms, *transform, value, &track.target)?;
+                    }
+                }
+
+                keys.push(MotionKey {
+                    time,
+                    transforms: transforms.unwrap_or_default(),
+                });
+            }
+
+            *object_mut(config, scene, object)?.1 = Some(keys);
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&config::Animation> for Animation {
+    fn from(config: &config::Animation) -> Self {
+        Self {
+            frames: config.frames.start..=config.frames.end,
+            fps: config.fps.unwrap_or(DEFAULT_FPS),
+            tracks: config.tracks.iter().map(Track::from).collect(),
+        }
+    }
+}
+
+/// The transforms and motion keys of an object
+type Placement<'a> = (
+    &'a mut Option<Vec<Transform>>,
+    &'a mut Option<Vec<MotionKey>>,
+);
+
+/// The transforms and motion of the `object`th object in `scene`
+fn object_mut<'a>(
+    config: &'a mut Config,
+    scene: Option<&str>,
+    object: usize,
+) -> Result<Placement<'a>, ConfigError> {
+    let (index, _) = config.scene(scene)?;
+
+    match config.scenes[index].objects.get_mut(object) {
+        Some(
+            Object::Sphere {
+                transforms, motion, ..
+            }
+            | Object::Plane {
+                transforms, motion, ..
+            }
+            | Object::Mesh {
+                transforms, motion, ..
+            }
+            | Object::MeshInstance {
+                transforms, motion, ..
+            },
+        ) => Ok((transforms, motion)),
+        None => Err(animation_error(format!(
+            "Animation track targets object {} which doesn't exist",
+            object
+        ))),
+    }
+}
+
+fn set_transform(
+    transforms: &mut Option<Vec<Transform>>,
+    index: usize,
+    value: Value,
+    target: &Target,
+) -> Result<(), ConfigError> {
+    let Some(transform) = transforms
+        .as_mut()
+        .and_then(|transforms| transforms.get_mut(index))
+    else {
+        return Err(animation_error(format!(
+            "Animation track for {:?} targets a transform which doesn't exist",
+            target
+        )));
+    };
+
+    match transform {
+        Transform::Translate { value: current } | Transform::Scale { value: current } => {
+            *current = value.vector(target)?
+        }
+        Transform::RotateX { value: current }
+        | Transform::RotateY { value: current }
+        | Transform::RotateZ { value: current } => *current = value.scalar(target)?,
+    }
+
+    Ok(())
+}
+
+fn animation_error(message: String) -> ConfigError {
+    ConfigError::new(message, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(interpolation: Interpolation, values: &[(f32, f32)]) -> Track {
+        Track {
+            target: Target::Material {
+                material: String::from("test"),
+                property: MaterialProperty::DiffuseColor,
+            },
+            keyframes: values
+                .iter()
+                .map(|&(frame, value)| Keyframe {
+                    frame,
+                    value: Value::Scalar(value),
+                    interpolation,
+                })
+                .collect(),
+        }
+    }
+
+    fn scalar_at(track: &Track, frame: f32) -> f32 {
+        match track.value_at(frame).unwrap() {
+            Value::Scalar(value) => value,
+            Value::Vector(_) => panic!("Expected a scalar"),
+        }
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let keyframes = [(1.0, 0.0), (11.0, 10.0), (21.0, 0.0)];
+        let step = track(Interpolation::Step, &keyframes);
+        let linear = track(Interpolation::Linear, &keyframes);
+        let bezier = track(Interpolation::Bezier, &keyframes);
+
+        assert_eq_within_bound!(scalar_at(&step, 6.0), 0.0, 1e-5);
+        assert_eq_within_bound!(scalar_at(&linear, 6.0), 5.0, 1e-5);
+        assert_eq_within_bound!(scalar_at(&linear, 16.0), 5.0, 1e-5);
+        // Eases out of the first keyframe, slower than linear early on
+        assert!(scalar_at(&bezier, 2.0) < scalar_at(&linear, 2.0));
+        for frame in [1.0, 11.0, 21.0] {
+            assert_eq_within_bound!(scalar_at(&bezier, frame), scalar_at(&linear, frame), 1e-5);
+        }
+
+        // The ends hold outside of the keyframes
+        assert_eq_within_bound!(scalar_at(&linear, -5.0), 0.0, 1e-5);
+        assert_eq_within_bound!(scalar_at(&step, 30.0), 0.0, 1e-5);
+    }
+
+    #[test]
+    fn test_apply_to_config() {
+        let mut config: Config = serde_json::from_str(
+            r#"{
+                "max_depth": 1,
+                "super_sampling": "Off",
+                "cameras": [{
+                    "type": "Perspective",
+                    "fov": 0.8,
+                    "width": 4,
+                    "height": 4,
+                    "position": [0, 0, 0],
+                    "look_at": [0, 0, -1],
+                    "up": [0, 1, 0]
+                }],
+                "scenes": [{
+                    "clear_color": [0, 0, 0],
+                    "ambient_color": [0, 0, 0],
+                    "objects": [{
+                        "type": "Sphere",
+                        "radius": 1,
+                        "transforms": [{ "type": "Translate", "value": [0, 0, -5] }]
+                    }],
+                    "lights": []
+                }],
+                "materials": [],
+                "animation": {
+                    "frames": { "start": 1, "end": 3 },
+                    "tracks": [
+                        {
+                            "type": "Camera",
+                            "property": "Fov",
+                            "keyframes": [{ "frame": 1, "value": 0.8 }, { "frame": 3, "value": 1.2 }]
+                        },
+                        {
+                            "type": "Object",
+                            "object": 0,
+                            "transform": 0,
+                            "keyframes": [
+                                { "frame": 1, "value": [0, 0, -5] },
+                                { "frame": 3, "value": [2, 0, -5] }
+                            ]
+                        }
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+        let animation = Animation::from_config(&config).unwrap().unwrap();
+
+        animation.apply(&mut config, 2.0).unwrap();
+
+        let Projection::Perspective { fov, .. } = config.cameras[0].projection else {
+            panic!("Expected a perspective camera");
+        };
+        assert!((fov - 1.0).abs() < 1e-5);
+        let Object::Sphere { transforms, .. } = &config.scenes[0].objects[0] else {
+            panic!("Expected a sphere");
+        };
+        let Some(Transform::Translate { value }) = transforms.as_ref().map(|t| &t[0]) else {
+            panic!("Expected a translation");
+        };
+        assert_eq_within_bound!(value[0], 1.0, 1e-5);
+
+        // The sphere keeps moving until the next frame
+        let Object::Sphere {
+            motion: Some(keys), ..
+        } = &config.scenes[0].objects[0]
+        else {
+            panic!("Expected motion keys");
+        };
+        let Transform::Translate { value } = &keys.last().unwrap().transforms[0] else {
+            panic!("Expected a translation");
+        };
+        assert_eq_within_bound!(keys.last().unwrap().time, 1.0, 1e-5);
+        assert_eq_within_bound!(value[0], 2.0, 1e-5);
+    }
+
+    #[test]
+    fn test_shutter_speed_sets_shutter() {
+        let mut config: Config = serde_json::from_str(
+            r#"{
+                "max_depth": 1,
+                "super_sampling": "Off",
+                "cameras": [{
+                    "type": "Physical",
+                    "focal_length": 50,
+                    "shutter_speed": 0.01,
+                    "width": 4,
+                    "height": 4,
+                    "position": [0, 0, 0],
+                    "look_at": [0, 0, -1],
+                    "up": [0, 1, 0]
+                }],
+                "scenes": [],
+                "materials": [],
+                "animation": {
+                    "frames": { "start": 1, "end": 3 },
+                    "fps": 50,
+                    "tracks": []
+                }
+            }"#,
+        )
+        .unwrap();
+        let animation = Animation::from_config(&config).unwrap().unwrap();
+
+        animation.apply(&mut config, 1.0).unwrap();
+
+        // A hundredth of a second is half of a frame at 50 frames per second
+        let shutter = config.cameras[0].shutter.unwrap();
+        assert_eq_within_bound!(shutter.open, 0.0, 1e-5);
+        assert_eq_within_bound!(shutter.close, 0.5, 1e-5);
+    }
+
+    #[test]
+    fn test_reject_motion_on_animated_object() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "max_depth": 1,
+                "super_sampling": "Off",
+                "cameras": [],
+                "scenes": [{
+                    "clear_color": [0, 0, 0],
+                    "ambient_color": [0, 0, 0],
+                    "objects": [{
+                        "type": "Sphere",
+                        "radius": 1,
+                        "transforms": [{ "type": "Translate", "value": [0, 0, -5] }],
+                        "motion": [{
+                            "time": 1,
+                            "transforms": [{ "type": "Translate", "value": [1, 0, -5] }]
+                        }]
+                    }],
+                    "lights": []
+                }],
+                "materials": [],
+                "animation": {
+                    "frames": { "start": 1, "end": 3 },
+                    "tracks": [{
+                        "type": "Object",
+                        "object": 0,
+                        "transform": 0,
+                        "keyframes": [
+                            { "frame": 1, "value": [0, 0, -5] },
+                            { "frame": 3, "value": [2, 0, -5] }
+                        ]
+                    }]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(Animation::from_config(&config).is_err());
+    }
+}
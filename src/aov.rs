@@ -0,0 +1,267 @@
+use std::path::Path;
+
+use exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, Image, ImageAttributes, IntegerBounds, Layer,
+    LayerAttributes, SmallVec, Vec2, WritableImage,
+};
+use serde::Deserialize;
+
+use crate::color::Color;
+use crate::framebuffer::{AovFilter, Framebuffer};
+use crate::material::Material;
+use crate::math::hash_all;
+use crate::tone_mapping::{linear_to_srgb, ToneMapping};
+
+/// Arbitrary output variables, extra buffers rendered alongside the beauty
+/// pass. Geometric AOVs are taken from the first intersection of each camera
+/// ray and are black where the ray misses the scene.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Aov {
+    /// Distance from the camera along its viewing direction
+    Depth,
+    /// World space shading normal
+    Normal,
+    /// Diffuse color, including textures
+    Albedo,
+    /// Texture coordinates in the red and green channels
+    Uv,
+    /// Light arriving directly from light sources, emissive surfaces and the
+    /// background
+    Direct,
+    /// Diffuse and glossy interreflections, with the Whitted integrator this
+    /// is the ambient term
+    Indirect,
+    /// Light arriving via mirror reflection off the first surface
+    Reflection,
+    /// Light arriving via refraction through the first surface
+    Refraction,
+    /// A unique color for each object in the scene. IDs aren't filtered,
+    /// each pixel shows the object seen by its most central sample.
+    ObjectId,
+    /// A unique color for each material, unfiltered like `ObjectId`
+    MaterialId,
+}
+
+/// How AOVs are written to disk.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AovFormat {
+    /// One PNG per AOV next to the beauty image
+    Separate,
+    /// A single OpenEXR file with the beauty pass and each AOV as a layer
+    Multilayer,
+}
+
+impl Aov {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::Uv => "uv",
+            Aov::Direct => "direct",
+            Aov::Indirect => "indirect",
+            Aov::Reflection => "reflection",
+            Aov::Refraction => "refraction",
+            Aov::ObjectId => "object_id",
+            Aov::MaterialId => "material_id",
+        }
+    }
+
+    /// How samples of the AOV are combined into pixels
+    pub fn filter(&self) -> AovFilter {
+        match *self {
+            Aov::ObjectId | Aov::MaterialId => AovFilter::Nearest,
+            _ => AovFilter::Filtered,
+        }
+    }
+}
+
+/// A stable, distinct color for an ID. Zero is reserved for the background.
+pub fn id_color(id: u64) -> Color {
+    if id == 0 {
+        return Color::black();
+    }
+
+    let hash = hash_all(&[id]);
+
+    Color::new_u8(hash as u8, (hash >> 8) as u8, (hash >> 16) as u8)
+}
+
+/// An ID derived from the parameters of a material. Materials that look the
+/// same share an ID.
+pub fn material_id(material: &Material) -> u64 {
+    let colors = [
+        material.ambient_color,
+        material.diffuse_color,
+        material.specular_color,
+    ];
+    let mut values: Vec<u64> = colors
+        .iter()
+        .flat_map(|color| [color.r(), color.g(), color.b()])
+        .map(|value| u64::from(value.to_bits()))
+        .collect();
+
+    values.push(u64::from(material.specular_exponent.to_bits()));
+    values.push(material.illumination_model as u64);
+    values.push(
+        material
+            .reflection_coefficient
+            .map_or(0, |c| c.to_bits().into()),
+    );
+    values.push(
+        material
+            .refraction_coefficient
+            .map_or(0, |c| c.to_bits().into()),
+    );
+
+    // Keep zero free for the background
+    hash_all(&values) | 1
+}
+
+/// Quantise the `index`-th AOV of `framebuffer` to an 8 bit RGB image suitable
+/// for viewing. Lighting AOVs are tone mapped like the beauty pass.
+pub fn develop(
+    framebuffer: &Framebuffer,
+    index: usize,
+    aov: Aov,
+    tone_mapping: &ToneMapping,
+) -> Vec<u8> {
+    let (width, height) = (framebuffer.width(), framebuffer.height());
+    let values = (0..height)
+        .flat_map(|y| (0..width).map(move |x| framebuffer.aov(index, x, y)))
+        .collect::<Vec<_>>();
+
+    let max_depth = values
+        .iter()
+        .map(|value| value.r())
+        .fold(0.0_f32, f32::max)
+        .max(f32::EPSILON);
+
+    values
+        .into_iter()
+        .flat_map(|value| {
+            let display = match aov {
+                Aov::Depth => value / max_depth,
+                Aov::Normal => value * 0.5 + Color::new(0.5, 0.5, 0.5),
+                Aov::Albedo => value.map(linear_to_srgb),
+                Aov::Uv | Aov::ObjectId | Aov::MaterialId => value,
+                Aov::Direct | Aov::Indirect | Aov::Reflection | Aov::Refraction => {
+                    tone_mapping.apply(value)
+                }
+            };
+
+            display.to_rgb8()
+        })
+        .collect()
+}
+
+/// Write the linear `beauty` pass and all AOVs as layers of a single OpenEXR
+/// file.
+pub fn write_multilayer(
+    path: &Path,
+    framebuffer: &Framebuffer,
+    beauty: &[Color],
+    aovs: &[Aov],
+) -> Result<(), exr::error::Error> {
+    let (width, height) = (framebuffer.width() as usize, framebuffer.height() as usize);
+    let size = Vec2(width, height);
+    let mut layers = vec![build_layer(size, "beauty", |x, y| {
+        beauty[y as usize * width + x as usize]
+    })];
+
+    for (index, aov) in aovs.iter().enumerate() {
+        layers.push(build_layer(size, aov.name(), |x, y| {
+            framebuffer.aov(index, x, y)
+        }));
+    }
+
+    Image::from_layers(
+        ImageAttributes::new(IntegerBounds::from_dimensions(size)),
+        layers,
+    )
+    .write()
+    .to_file(path)
+}
+
+fn build_layer<F>(size: Vec2<usize>, name: &str, lookup: F) -> Layer<AnyChannels<FlatSamples>>
+where
+    F: Fn(u32, u32) -> Color,
+{
+    let mut channels = [
+        Vec::with_capacity(size.area()),
+        Vec::with_capacity(size.area()),
+        Vec::with_capacity(size.area()),
+    ];
+
+    for y in 0..size.height() {
+        for x in 0..size.width() {
+            let value = lookup(x as u32, y as u32);
+
+            channels[0].push(value.r());
+            channels[1].push(value.g());
+            channels[2].push(value.b());
+        }
+    }
+
+    let [r, g, b] = channels;
+    let channels: SmallVec<[AnyChannel<FlatSamples>; 4]> = SmallVec::from_vec(vec![
+        AnyChannel::new("R", FlatSamples::F32(r)),
+        AnyChannel::new("G", FlatSamples::F32(g)),
+        AnyChannel::new("B", FlatSamples::F32(b)),
+    ]);
+
+    Layer::new(
+        size,
+        LayerAttributes::named(name),
+        Encoding::FAST_LOSSLESS,
+        AnyChannels::sort(channels),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::IllumninationModel;
+
+    #[test]
+    fn test_id_colors_are_distinct() {
+        assert_eq!(id_color(0), Color::black());
+        assert_ne!(id_color(1), id_color(2));
+        assert_eq!(id_color(7), id_color(7));
+    }
+
+    #[test]
+    fn test_material_id() {
+        let red = Material::new(
+            Color::black(),
+            Color::red(),
+            Color::white(),
+            20.0,
+            IllumninationModel::DiffuseSpecular,
+            None,
+            None,
+        );
+        let same_red = Material::new(
+            Color::black(),
+            Color::red(),
+            Color::white(),
+            20.0,
+            IllumninationModel::DiffuseSpecular,
+            None,
+            None,
+        );
+        let blue = Material::new(
+            Color::black(),
+            Color::blue(),
+            Color::white(),
+            20.0,
+            IllumninationModel::DiffuseSpecular,
+            None,
+            None,
+        );
+
+        assert_eq!(material_id(&red), material_id(&same_red));
+        assert_ne!(material_id(&red), material_id(&blue));
+        assert_ne!(material_id(&red), 0);
+    }
+}
@@ -0,0 +1,113 @@
+use std::f32::consts::PI;
+use std::path::Path;
+
+use crate::math::{concentric_disk, Distribution2D, Point2};
+
+/// The shape of the lens opening. Out of focus highlights take on this shape.
+#[derive(Debug)]
+pub enum Aperture {
+    Circle,
+    /// A regular polygon, as formed by the blades of a diaphragm
+    Polygon {
+        blades: u32,
+        /// Rotation of the first corner away from the camera's up axis, in
+        /// radians
+        rotation: f32,
+    },
+    Mask(ApertureMask),
+}
+
+impl Aperture {
+    /// Map `u` in [0, 1)² to a point on the aperture. The aperture fits
+    /// within the unit circle, or the [-1, 1]² square for masks.
+    pub fn sample(&self, u: Point2) -> Point2 {
+        match self {
+            Aperture::Circle => concentric_disk(u),
+            Aperture::Polygon { blades, rotation } => sample_polygon(*blades, *rotation, u),
+            Aperture::Mask(mask) => mask.sample(u),
+        }
+    }
+}
+
+/// A grayscale image where white is open and black is blocked.
+#[derive(Debug)]
+pub struct ApertureMask {
+    distribution: Distribution2D,
+}
+
+impl ApertureMask {
+    pub fn new(path: &Path) -> Result<Self, image::ImageError> {
+        let image = image::open(path)?.to_luma8();
+        let values: Vec<f32> = image.pixels().map(|p| f32::from(p[0]) / 255.0).collect();
+
+        Ok(Self {
+            distribution: Distribution2D::new(
+                &values,
+                image.width() as usize,
+                image.height() as usize,
+            ),
+        })
+    }
+
+    fn sample(&self, u: Point2) -> Point2 {
+        let (p, _) = self.distribution.sample(u);
+
+        // The first row of the image is the top of the aperture
+        Point2::new(2.0 * p.x - 1.0, 1.0 - 2.0 * p.y)
+    }
+}
+
+/// Split the polygon into triangles fanning out from its center, pick one
+/// with `u.x` and sample it uniformly.
+fn sample_polygon(blades: u32, rotation: f32, u: Point2) -> Point2 {
+    let blades = blades.max(3);
+    let scaled = u.x * blades as f32;
+    let blade = (scaled as u32).min(blades - 1);
+    let (a, b) = (scaled - blade as f32, u.y);
+
+    let corner = |index: u32| {
+        let angle = rotation + 2.0 * PI * index as f32 / blades as f32;
+        // Start at the top so that rotation zero looks upright
+        Point2::new(-angle.sin(), angle.cos())
+    };
+    let (first, second) = (corner(blade), corner(blade + 1));
+
+    // Uniform barycentric coordinates for the triangle (center, first, second)
+    let s = a.sqrt();
+    let (w1, w2) = (s * (1.0 - b), s * b);
+
+    Point2::new(first.x * w1 + second.x * w2, first.y * w1 + second.y * w2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polygon_samples_stay_inside() {
+        let blades = 6;
+        // The inscribed circle of a hexagon with unit circumradius
+        let apothem = (PI / blades as f32).cos();
+        let mut outside_apothem = 0;
+
+        for i in 0..32 {
+            for j in 0..32 {
+                let u = Point2::new((i as f32 + 0.5) / 32.0, (j as f32 + 0.5) / 32.0);
+                let p = Aperture::Polygon {
+                    blades,
+                    rotation: 0.3,
+                }
+                .sample(u);
+                let radius = (p.x * p.x + p.y * p.y).sqrt();
+
+                assert!(radius <= 1.0 + 1e-5);
+                if radius > apothem {
+                    outside_apothem += 1;
+                }
+            }
+        }
+
+        // Some samples must reach into the corners
+        assert!(outside_apothem > 0);
+    }
+}
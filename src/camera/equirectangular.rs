@@ -0,0 +1,94 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use super::{Camera, Frame};
+use crate::math::{Point2, Point3, Vector3};
+use crate::ray::Ray;
+
+/// A full 360° × 180° panorama in latitude-longitude layout. The center of the
+/// image looks along the viewing direction, the left and right edges look
+/// backwards. Images usually have an aspect ratio of 2:1.
+#[derive(Debug)]
+pub struct Equirectangular {
+    frame: Frame,
+}
+
+impl Equirectangular {
+    pub fn new(frame: Frame) -> Self {
+        Self { frame }
+    }
+}
+
+impl Camera for Equirectangular {
+    fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    fn create_ray(&self, x: u32, y: u32, offset: Point2, _lens_sample: Point2) -> Option<Ray> {
+        let film = self.frame.film_position(x, y, offset);
+        let (longitude, latitude) = (film.x * PI, film.y * FRAC_PI_2);
+        let direction = Vector3::new(
+            longitude.sin() * latitude.cos(),
+            latitude.sin(),
+            -longitude.cos() * latitude.cos(),
+        );
+
+        // Omni-directional stereo, the eyes sit on a circle and are always
+        // separated perpendicular to the direction they look in
+        let axis = Vector3::new(longitude.cos(), 0.0, longitude.sin());
+        let (origin, direction) = self.frame.eye_ray(direction, axis);
+
+        Some(self.frame.ray(origin, direction))
+    }
+
+    /// Panoramas have no single viewing direction, this is the distance from
+    /// the camera
+    fn depth(&self, ray: &Ray, point: Point3) -> f32 {
+        (point - ray.origin).length()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_covers_full_sphere() {
+        let frame = Frame::new(
+            200,
+            100,
+            Point3::at_origin(),
+            Point3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        let camera = Equirectangular::new(frame);
+        let direction = |x, y, offset| {
+            camera
+                .create_ray(x, y, offset, Point2::at_origin())
+                .unwrap()
+                .direction
+        };
+
+        // The center looks ahead, a quarter of the way right looks right, the
+        // top row looks up and the right edge looks backwards
+        assert_eq_vector3!(
+            direction(100, 50, Point2::at_origin()),
+            Vector3::new(0.0, 0.0, -1.0),
+            1e-5
+        );
+        assert_eq_vector3!(
+            direction(150, 50, Point2::at_origin()),
+            Vector3::new(1.0, 0.0, 0.0),
+            1e-5
+        );
+        assert_eq_vector3!(
+            direction(100, 99, Point2::new(0.0, 1.0)),
+            Vector3::new(0.0, 1.0, 0.0),
+            1e-5
+        );
+        assert_eq_vector3!(
+            direction(199, 50, Point2::new(1.0, 0.0)),
+            Vector3::new(0.0, 0.0, 1.0),
+            1e-5
+        );
+    }
+}
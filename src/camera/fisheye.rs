@@ -0,0 +1,143 @@
+use serde::Deserialize;
+
+use super::{Camera, Frame};
+use crate::math::{Point2, Point3, Vector3};
+use crate::ray::Ray;
+
+/// How the angle from the optical axis maps to the distance from the center
+/// of the image circle.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FisheyeMapping {
+    /// The distance is proportional to the angle, r = f θ
+    Equidistant,
+    /// Preserves areas, r = 2 f sin(θ / 2)
+    Equisolid,
+}
+
+/// A circular fisheye, the image circle fills the shorter side of the image
+/// and covers a field of view of `fov` radians, which can exceed π. Pixels
+/// outside of the circle stay black.
+#[derive(Debug)]
+pub struct Fisheye {
+    frame: Frame,
+    fov: f32,
+    mapping: FisheyeMapping,
+}
+
+impl Fisheye {
+    pub fn new(frame: Frame, fov: f32, mapping: FisheyeMapping) -> Self {
+        Self {
+            frame,
+            fov,
+            mapping,
+        }
+    }
+
+    /// The angle from the optical axis at `radius`, where the edge of the
+    /// image circle is at a radius of one
+    fn angle(&self, radius: f32) -> f32 {
+        let max_angle = self.fov * 0.5;
+
+        match self.mapping {
+            FisheyeMapping::Equidistant => radius * max_angle,
+            FisheyeMapping::Equisolid => {
+                2.0 * (radius * (max_angle * 0.5).sin()).clamp(-1.0, 1.0).asin()
+            }
+        }
+    }
+}
+
+impl Camera for Fisheye {
+    fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    fn create_ray(&self, x: u32, y: u32, offset: Point2, _lens_sample: Point2) -> Option<Ray> {
+        let film = self.frame.film_position(x, y, offset);
+        // Keep pixels square, the circle touches the shorter side
+        let aspect_ratio = self.frame.aspect_ratio();
+        let (u, v) = if aspect_ratio >= 1.0 {
+            (film.x * aspect_ratio, film.y)
+        } else {
+            (film.x, film.y / aspect_ratio)
+        };
+        let radius = (u * u + v * v).sqrt();
+        if radius > 1.0 {
+            return None;
+        }
+
+        let theta = self.angle(radius);
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let direction = if radius > 0.0 {
+            Vector3::new(sin_theta * u / radius, sin_theta * v / radius, -cos_theta)
+        } else {
+            Vector3::new(0.0, 0.0, -1.0)
+        };
+
+        let (origin, direction) = self.frame.eye_ray(direction, Vector3::new(1.0, 0.0, 0.0));
+
+        Some(self.frame.ray(origin, direction))
+    }
+
+    /// Fisheyes can see behind themselves, this is the distance from the
+    /// camera
+    fn depth(&self, ray: &Ray, point: Point3) -> f32 {
+        (point - ray.origin).length()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn fisheye(mapping: FisheyeMapping) -> Fisheye {
+        let frame = Frame::new(
+            100,
+            100,
+            Point3::at_origin(),
+            Point3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+
+        Fisheye::new(frame, PI, mapping)
+    }
+
+    #[test]
+    fn test_edge_of_circle_is_at_half_fov() {
+        for mapping in [FisheyeMapping::Equidistant, FisheyeMapping::Equisolid] {
+            let camera = fisheye(mapping);
+            // The right edge of the middle row
+            let ray = camera
+                .create_ray(99, 50, Point2::new(1.0, 0.0), Point2::at_origin())
+                .unwrap();
+
+            assert_eq_vector3!(ray.direction, Vector3::new(1.0, 0.0, 0.0), 1e-4);
+
+            let center = camera
+                .create_ray(50, 50, Point2::at_origin(), Point2::at_origin())
+                .unwrap();
+            assert_eq_vector3!(center.direction, Vector3::new(0.0, 0.0, -1.0), 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_corners_are_outside_circle() {
+        let camera = fisheye(FisheyeMapping::Equidistant);
+
+        assert!(camera
+            .create_ray(0, 0, Point2::at_origin(), Point2::at_origin())
+            .is_none());
+    }
+
+    #[test]
+    fn test_equisolid_magnifies_center() {
+        let equidistant = fisheye(FisheyeMapping::Equidistant);
+        let equisolid = fisheye(FisheyeMapping::Equisolid);
+
+        // More of the image circle is spent on the center, so halfway to the
+        // edge the equisolid projection hasn't turned as far
+        assert!(equisolid.angle(0.5) < equidistant.angle(0.5));
+        assert_eq_within_bound!(equisolid.angle(1.0), PI * 0.5, 1e-5);
+    }
+}
@@ -0,0 +1,140 @@
+use std::f32::consts::FRAC_PI_2;
+
+use super::Physical;
+use crate::config::{self, ConfigError};
+use crate::geometry::AABB;
+use crate::math::{Point3, Vector3};
+
+/// Move the camera described by `config` so that `bounds` fill its image as
+/// described by `framing`. The camera looks at the center of the bounds from
+/// just far enough away for every corner to be in view, orthographic cameras
+/// are resized instead.
+pub fn frame(
+    config: &mut config::Camera,
+    framing: &config::Framing,
+    bounds: &AABB,
+) -> Result<(), ConfigError> {
+    let forward = match framing.direction {
+        Some(direction) => Vector3::from(direction),
+        None => Point3::from(config.look_at) - Point3::from(config.position),
+    }
+    .normalize();
+    let right = forward.cross(&Vector3::from(config.up));
+    if right.length() < 1e-6 || forward.x.is_nan() {
+        return Err(framing_error(
+            "needs a direction that isn't parallel to the camera's up",
+        ));
+    }
+    let right = right.normalize();
+    let up = right.cross(&forward);
+
+    let center = bounds.center();
+    let corners = bounds.corners().map(|corner| {
+        let offset = corner - center;
+
+        (offset.dot(&right), offset.dot(&up), offset.dot(&forward))
+    });
+    let aspect_ratio = config.width as f32 / config.height as f32;
+    let fill = 1.0 - framing.padding.unwrap_or(0.0).clamp(0.0, 0.99);
+
+    // The distance at which a corner (x, y, z) is just inside of a frustum
+    // that is `tan_x` wide and `tan_y` high at a distance of one
+    let fit_frustum = |tan_x: f32, tan_y: f32| {
+        corners
+            .iter()
+            .map(|(x, y, z)| (x.abs() / tan_x).max(y.abs() / tan_y) - z)
+            .fold(0.0, f32::max)
+    };
+
+    let distance = match &mut config.projection {
+        config::Projection::Perspective { fov, .. } => {
+            let tan_y = (*fov * 0.5).tan() * fill;
+
+            fit_frustum(tan_y * aspect_ratio, tan_y)
+        }
+        config::Projection::Physical(physical) => {
+            let fov = Physical::from(&*physical).fov(aspect_ratio);
+            let tan_y = (fov * 0.5).tan() * fill;
+
+            fit_frustum(tan_y * aspect_ratio, tan_y)
+        }
+        config::Projection::Fisheye { fov, .. } => {
+            // The image circle touches the shorter side, beyond a right angle
+            // the corners are in view from any distance
+            let tan_radius = (*fov * 0.5 * fill).min(FRAC_PI_2 * 0.9).tan();
+
+            corners
+                .iter()
+                .map(|(x, y, z)| (x * x + y * y).sqrt() / tan_radius - z)
+                .fold(0.0, f32::max)
+        }
+        config::Projection::Orthographic { view_width } => {
+            *view_width = corners
+                .iter()
+                .map(|(x, y, _)| (2.0 * x.abs()).max(2.0 * y.abs() * aspect_ratio) / fill)
+                .fold(0.0, f32::max);
+
+            bounds.dimensions().length()
+        }
+        config::Projection::Equirectangular => {
+            return Err(framing_error(
+                "isn't possible with an equirectangular camera",
+            ))
+        }
+    };
+
+    let position = (center + forward * -distance).as_point();
+    config.look_at = [center.x, center.y, center.z];
+    config.position = [position.x, position.y, position.z];
+
+    Ok(())
+}
+
+fn framing_error(reason: &str) -> ConfigError {
+    ConfigError::new(format!("Framing the camera {}", reason), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn framing(padding: Option<f32>) -> config::Framing {
+        config::Framing {
+            target: String::from("all"),
+            direction: Some([0.0, 0.0, -1.0]),
+            padding,
+        }
+    }
+
+    fn unit_box() -> AABB {
+        AABB::new(Point3::new(4.0, -1.0, -1.0), Point3::new(6.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_perspective_fits_nearest_face() {
+        let mut camera =
+            config::Camera::new(FRAC_PI_2, 100, 100, [0.0; 3], [0.0; 3], [0.0, 1.0, 0.0]);
+        frame(&mut camera, &framing(None), &unit_box()).unwrap();
+
+        // The front face spans a 90° field of view one unit in front of it
+        assert_eq!(camera.look_at, [5.0, 0.0, 0.0]);
+        assert_eq_within_bound!(camera.position[0], 5.0, 1e-5);
+        assert_eq_within_bound!(camera.position[2], 2.0, 1e-5);
+
+        frame(&mut camera, &framing(Some(0.5)), &unit_box()).unwrap();
+        assert_eq_within_bound!(camera.position[2], 3.0, 1e-5);
+    }
+
+    #[test]
+    fn test_orthographic_resizes_view() {
+        let mut camera = config::Camera::new(1.0, 200, 100, [0.0; 3], [0.0; 3], [0.0, 1.0, 0.0]);
+        camera.projection = config::Projection::Orthographic { view_width: 1.0 };
+        frame(&mut camera, &framing(None), &unit_box()).unwrap();
+
+        // The box is as high as the image, which is twice as wide as high
+        let config::Projection::Orthographic { view_width } = camera.projection else {
+            unreachable!();
+        };
+        assert!((view_width - 4.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,264 @@
+mod aperture;
+mod equirectangular;
+mod fisheye;
+mod framing;
+mod orthographic;
+mod perspective;
+mod physical;
+mod stereo;
+
+use std::fmt;
+use std::path::Path;
+
+use crate::config;
+use crate::math::{Matrix4, Point2, Point3, Vector3};
+use crate::ray::Ray;
+use crate::scheduler::Tile;
+
+pub use self::aperture::{Aperture, ApertureMask};
+pub use self::equirectangular::Equirectangular;
+pub use self::fisheye::{Fisheye, FisheyeMapping};
+pub use self::framing::frame;
+pub use self::orthographic::Orthographic;
+pub use self::perspective::{Lens, Perspective};
+pub use self::physical::{Physical, DEFAULT_WHITE_BALANCE};
+pub use self::stereo::{Eye, Stereo, StereoLayout};
+
+/// Maps positions on the film to rays leaving the camera.
+///
+/// Cameras look down their negative z axis with y up, the film position
+/// (0, 0) is the bottom left corner of the image.
+pub trait Camera: fmt::Debug {
+    /// The size and placement of the image, for cameras with several views
+    /// the frame of the first one
+    fn frame(&self) -> &Frame;
+
+    fn width(&self) -> u32 {
+        self.frame().width
+    }
+
+    fn height(&self) -> u32 {
+        self.frame().height
+    }
+
+    /// Create a ray through the pixel at (`x`, `y`), `offset` is the position
+    /// within the pixel and `lens_sample` the position on the lens, both in
+    /// [0, 1)². Returns `None` for film positions the projection doesn't
+    /// cover, like the corners of a circular fisheye image.
+    fn create_ray(&self, x: u32, y: u32, offset: Point2, lens_sample: Point2) -> Option<Ray>;
+
+    /// When the rays are sent within the frame
+    fn shutter(&self) -> Shutter {
+        self.frame().shutter
+    }
+
+    /// The distance from the camera to `point`, the hit point of `ray`, as
+    /// stored in the depth AOV
+    fn depth(&self, ray: &Ray, point: Point3) -> f32;
+
+    /// Regions of the image that are separate views of the scene, like the
+    /// two eyes of a stereo rig. Filters don't blend pixels across the edges
+    /// of a view.
+    fn views(&self) -> Vec<Tile> {
+        vec![Tile {
+            x: 0,
+            y: 0,
+            width: self.width(),
+            height: self.height(),
+        }]
+    }
+}
+
+/// Build the camera described by `config`, paths in the config are relative
+/// to `base_path`.
+pub fn from_config(
+    config: &config::Camera,
+    base_path: &Path,
+) -> Result<Box<dyn Camera>, image::ImageError> {
+    match &config.stereo {
+        None => build_projection(config, base_path, None),
+        Some(stereo) => {
+            let eye = |offset| Eye {
+                offset,
+                convergence_distance: stereo.convergence_distance,
+            };
+            let half_distance = stereo.interocular_distance * 0.5;
+
+            Ok(Box::new(Stereo::new(
+                build_projection(config, base_path, Some(eye(-half_distance)))?,
+                build_projection(config, base_path, Some(eye(half_distance)))?,
+                stereo.layout.unwrap_or(StereoLayout::SideBySide),
+            )))
+        }
+    }
+}
+
+fn build_projection(
+    config: &config::Camera,
+    base_path: &Path,
+    eye: Option<Eye>,
+) -> Result<Box<dyn Camera>, image::ImageError> {
+    let mut frame = Frame::new(
+        config.width,
+        config.height,
+        Point3::from(config.position),
+        Point3::from(config.look_at),
+        Vector3::from(config.up),
+    );
+    frame.eye = eye;
+    if let Some(shutter) = config.shutter {
+        frame.shutter = Shutter::from(&shutter);
+    }
+
+    Ok(match &config.projection {
+        config::Projection::Perspective { .. } | config::Projection::Physical(_) => {
+            Box::new(Perspective::new_from_config(frame, config, base_path)?)
+        }
+        config::Projection::Orthographic { view_width } => {
+            Box::new(Orthographic::new(frame, *view_width))
+        }
+        config::Projection::Equirectangular => Box::new(Equirectangular::new(frame)),
+        config::Projection::Fisheye { fov, mapping } => Box::new(Fisheye::new(
+            frame,
+            *fov,
+            mapping.unwrap_or(FisheyeMapping::Equidistant),
+        )),
+    })
+}
+
+/// The interval within the frame the camera's shutter is open, 0 is the start
+/// of the frame and 1 the start of the next one. Objects that move while it is
+/// open are blurred.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Shutter {
+    pub open: f32,
+    pub close: f32,
+}
+
+impl Shutter {
+    /// The time of a ray for a uniform `sample` in [0, 1)
+    pub fn time(&self, sample: f32) -> f32 {
+        self.open + (self.close - self.open) * sample
+    }
+}
+
+impl From<&config::Shutter> for Shutter {
+    fn from(config: &config::Shutter) -> Self {
+        Self {
+            open: config.open,
+            close: config.close,
+        }
+    }
+}
+
+/// The size of the image and the placement of the camera in the world, shared
+/// by all projections.
+#[derive(Debug)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    /// Set when the camera is one eye of a stereo rig
+    pub eye: Option<Eye>,
+    /// Closed at the start of the frame unless set
+    pub shutter: Shutter,
+    camera_to_world: Matrix4,
+}
+
+impl Frame {
+    pub fn new(
+        width: u32,
+        height: u32,
+        position: Point3,
+        look_at: Point3,
+        tmp_up: Vector3,
+    ) -> Self {
+        let direction = (position - look_at).normalize();
+        let right = tmp_up.normalize().cross(&direction);
+        let up = direction.cross(&right);
+
+        Self {
+            width,
+            height,
+            eye: None,
+            shutter: Shutter::default(),
+            camera_to_world: camera_to_world_matrix(
+                right.normalize(),
+                up.normalize(),
+                direction,
+                position,
+            ),
+        }
+    }
+
+    /// The position of the sample on the film in [-1, 1]², y up
+    fn film_position(&self, x: u32, y: u32, offset: Point2) -> Point2 {
+        Point2::new(
+            2.0 * (x as f32 + offset.x) / self.width as f32 - 1.0,
+            2.0 * (y as f32 + offset.y) / self.height as f32 - 1.0,
+        )
+    }
+
+    fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    /// Move a ray leaving the center of the camera along `direction` to the
+    /// eye, if any. The eyes are separated along `axis`. Returns the position
+    /// of the eye and the new direction, both in camera space.
+    fn eye_ray(&self, direction: Vector3, axis: Vector3) -> (Point3, Vector3) {
+        match self.eye {
+            None => (Point3::at_origin(), direction),
+            Some(eye) => eye.apply(direction, axis),
+        }
+    }
+
+    /// Transform a ray from camera to world space
+    fn ray(&self, origin: Point3, direction: Vector3) -> Ray {
+        Ray::new(
+            self.camera_to_world * origin,
+            (self.camera_to_world * direction).normalize(),
+            None,
+        )
+    }
+
+    /// Distance from the camera to `point` along the viewing direction
+    fn view_depth(&self, point: Point3) -> f32 {
+        // The camera looks down its negative z axis
+        let backward = self.camera_to_world * Vector3::new(0.0, 0.0, 1.0);
+
+        let position = self.camera_to_world * Point3::at_origin();
+
+        (position - point).dot(&backward.normalize())
+    }
+}
+
+fn camera_to_world_matrix(
+    right: Vector3,
+    up: Vector3,
+    direction: Vector3,
+    position: Point3,
+) -> Matrix4 {
+    let mut result = Matrix4::identity();
+
+    // right
+    result[(0, 0)] = right.x;
+    result[(0, 1)] = right.y;
+    result[(0, 2)] = right.z;
+
+    // up
+    result[(1, 0)] = up.x;
+    result[(1, 1)] = up.y;
+    result[(1, 2)] = up.z;
+
+    // direction
+    result[(2, 0)] = direction.x;
+    result[(2, 1)] = direction.y;
+    result[(2, 2)] = direction.z;
+
+    // position
+    result[(3, 0)] = position.x;
+    result[(3, 1)] = position.y;
+    result[(3, 2)] = position.z;
+
+    result
+}
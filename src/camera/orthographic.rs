@@ -0,0 +1,77 @@
+use super::{Camera, Frame};
+use crate::math::{Point2, Point3, Vector3};
+use crate::ray::Ray;
+
+/// Parallel rays covering a `view_width` wide rectangle, the height follows
+/// from the image's aspect ratio. Lines that are parallel in the scene stay
+/// parallel in the image.
+#[derive(Debug)]
+pub struct Orthographic {
+    frame: Frame,
+    view_width: f32,
+}
+
+impl Orthographic {
+    pub fn new(frame: Frame, view_width: f32) -> Self {
+        Self { frame, view_width }
+    }
+}
+
+impl Camera for Orthographic {
+    fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    fn create_ray(&self, x: u32, y: u32, offset: Point2, _lens_sample: Point2) -> Option<Ray> {
+        let film = self.frame.film_position(x, y, offset);
+        let half_width = self.view_width * 0.5;
+        let origin = Point3::new(
+            film.x * half_width,
+            film.y * half_width / self.frame.aspect_ratio(),
+            0.0,
+        );
+
+        let (eye, direction) = self
+            .frame
+            .eye_ray(Vector3::new(0.0, 0.0, -1.0), Vector3::new(1.0, 0.0, 0.0));
+
+        Some(
+            self.frame
+                .ray((origin + eye.as_vector()).as_point(), direction),
+        )
+    }
+
+    fn depth(&self, _ray: &Ray, point: Point3) -> f32 {
+        self.frame.view_depth(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rays_are_parallel_and_cover_view_width() {
+        let frame = Frame::new(
+            100,
+            50,
+            Point3::at_origin(),
+            Point3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        let camera = Orthographic::new(frame, 4.0);
+
+        // The bottom left corner and the right edge of the middle row
+        let corner = camera
+            .create_ray(0, 0, Point2::at_origin(), Point2::at_origin())
+            .unwrap();
+        let edge = camera
+            .create_ray(99, 25, Point2::new(1.0, 0.0), Point2::at_origin())
+            .unwrap();
+
+        assert_eq_point3!(corner.origin, Point3::new(-2.0, -1.0, 0.0), 1e-5);
+        assert_eq_point3!(edge.origin, Point3::new(2.0, 0.0, 0.0), 1e-5);
+        assert_eq_vector3!(corner.direction, Vector3::new(0.0, 0.0, -1.0), 1e-5);
+        assert_eq_vector3!(edge.direction, Vector3::new(0.0, 0.0, -1.0), 1e-5);
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::Path;
+
+use super::{Aperture, ApertureMask, Camera, Frame, Physical};
+use crate::config;
+use crate::math::{Point2, Point3, Vector3};
+use crate::ray::Ray;
+
+/// Height of a full frame sensor in metres, used to derive the focal length
+/// from the field of view when the aperture is given as an f-stop and the
+/// config doesn't set a sensor height.
+const DEFAULT_SENSOR_HEIGHT: f32 = 0.024;
+
+/// A thin lens, points at `focus_distance` are in perfect focus and
+/// everything else is blurred in proportion to the `radius`.
+#[derive(Debug)]
+pub struct Lens {
+    pub radius: f32,
+    pub focus_distance: f32,
+    pub aperture: Aperture,
+}
+
+/// A perspective projection with a vertical field of view of `fov` radians.
+#[derive(Debug)]
+pub struct Perspective {
+    frame: Frame,
+    scale: f32,
+    /// Without a lens the camera is a pinhole and everything is in focus
+    lens: Option<Lens>,
+}
+
+impl Perspective {
+    pub fn new(frame: Frame, fov: f32) -> Self {
+        Self {
+            frame,
+            scale: (fov * 0.5).tan(),
+            lens: None,
+        }
+    }
+
+    pub fn with_lens(mut self, lens: Lens) -> Self {
+        self.lens = Some(lens);
+        self
+    }
+
+    pub(super) fn new_from_config(
+        frame: Frame,
+        config: &config::Camera,
+        base_path: &Path,
+    ) -> Result<Self, image::ImageError> {
+        let (fov, diameter, focus_distance, focus_on, aperture_shape) = match &config.projection {
+            config::Projection::Perspective {
+                fov,
+                aperture,
+                f_stop,
+                sensor_height,
+                focus_distance,
+                focus_on,
+                aperture_shape,
+            } => {
+                let diameter = aperture.or_else(|| {
+                    let sensor_height = sensor_height.unwrap_or(DEFAULT_SENSOR_HEIGHT);
+                    let focal_length = sensor_height * 0.5 / (fov * 0.5).tan();
+                    f_stop.map(|f_stop| focal_length / f_stop)
+                });
+
+                (*fov, diameter, *focus_distance, *focus_on, aperture_shape)
+            }
+            config::Projection::Physical(physical_config) => {
+                let physical = Physical::from(physical_config);
+
+                (
+                    physical.fov(frame.aspect_ratio()),
+                    physical.aperture(),
+                    physical_config.focus_distance,
+                    physical_config.focus_on,
+                    &physical_config.aperture_shape,
+                )
+            }
+            _ => unreachable!("Expected a perspective camera config"),
+        };
+        let camera = Self::new(frame, fov);
+        let Some(diameter) = diameter else {
+            return Ok(camera);
+        };
+
+        let focus_distance = match (focus_distance, focus_on) {
+            (Some(distance), _) => distance,
+            (None, Some(point)) => camera.frame.view_depth(Point3::from(point)),
+            (None, None) => camera.frame.view_depth(Point3::from(config.look_at)),
+        };
+        let aperture = match aperture_shape {
+            None | Some(config::ApertureShape::Circle) => Aperture::Circle,
+            Some(config::ApertureShape::Polygon { blades, rotation }) => Aperture::Polygon {
+                blades: *blades,
+                rotation: rotation.unwrap_or(0.0),
+            },
+            Some(config::ApertureShape::Image { path }) => {
+                Aperture::Mask(ApertureMask::new(&base_path.join(path))?)
+            }
+        };
+
+        Ok(camera.with_lens(Lens {
+            radius: diameter * 0.5,
+            focus_distance,
+            aperture,
+        }))
+    }
+}
+
+impl Camera for Perspective {
+    fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    fn create_ray(&self, x: u32, y: u32, offset: Point2, lens_sample: Point2) -> Option<Ray> {
+        let film = self.frame.film_position(x, y, offset);
+        let pinhole_direction = Vector3::new(
+            film.x * self.frame.aspect_ratio() * self.scale,
+            film.y * self.scale,
+            -1.0,
+        );
+
+        let (eye, pinhole_direction) = self
+            .frame
+            .eye_ray(pinhole_direction, Vector3::new(1.0, 0.0, 0.0));
+
+        let (origin, direction) = match &self.lens {
+            None => (eye, pinhole_direction),
+            Some(lens) => {
+                // All rays through the lens converge where the pinhole ray
+                // crosses the plane of focus
+                let focus = (eye + pinhole_direction * lens.focus_distance).as_point();
+                let on_aperture = lens.aperture.sample(lens_sample);
+                let origin = (eye
+                    + Vector3::new(
+                        on_aperture.x * lens.radius,
+                        on_aperture.y * lens.radius,
+                        0.0,
+                    ))
+                .as_point();
+
+                (origin, focus - origin)
+            }
+        };
+
+        Some(self.frame.ray(origin, direction))
+    }
+
+    fn depth(&self, _ray: &Ray, point: Point3) -> f32 {
+        self.frame.view_depth(point)
+    }
+}
@@ -0,0 +1,142 @@
+use crate::config;
+use crate::light::sky::LUMINANCE_SCALE;
+
+/// Width and height of a full frame sensor in millimetres
+const FULL_FRAME_SENSOR: (f32, f32) = (36.0, 24.0);
+
+/// Luminance in cd/m² that saturates the sensor at an EV100 of 0, from the
+/// saturation based ISO speed with a lens transmittance of 0.65, 78 / 65
+const SATURATION_LUMINANCE: f32 = 1.2;
+
+/// The colour temperature that appears white unless set, D65
+pub const DEFAULT_WHITE_BALANCE: f32 = 6504.0;
+
+/// The settings of a real camera, from which the field of view, depth of field
+/// and exposure of a perspective projection are derived. Lengths are in
+/// millimetres.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Physical {
+    pub focal_length: f32,
+    pub sensor_width: f32,
+    pub sensor_height: f32,
+    /// Without an f-stop the lens is a pinhole and everything is in focus
+    pub f_stop: Option<f32>,
+    /// Exposure time in seconds, motion blur is controlled by the camera's
+    /// shutter interval instead. Animations derive that interval from this
+    /// when the config doesn't set one.
+    pub shutter_speed: f32,
+    pub iso: f32,
+    /// Colour temperature in Kelvin that appears white
+    pub white_balance: f32,
+}
+
+impl Physical {
+    /// The vertical field of view in radians of an image with `aspect_ratio`.
+    /// The image is the largest crop of the sensor with that aspect ratio.
+    pub fn fov(&self, aspect_ratio: f32) -> f32 {
+        let film_height = self.sensor_height.min(self.sensor_width / aspect_ratio);
+
+        2.0 * (film_height * 0.5 / self.focal_length).atan()
+    }
+
+    /// Diameter of the lens opening in metres
+    pub fn aperture(&self) -> Option<f32> {
+        self.f_stop.map(|f_stop| self.focal_length * 0.001 / f_stop)
+    }
+
+    /// Exposure value of the settings at ISO 100, f/1 for one second is 0. A
+    /// pinhole is exposed like f/1.
+    pub fn ev100(&self) -> f32 {
+        let f_stop = self.f_stop.unwrap_or(1.0);
+
+        (f_stop * f_stop / self.shutter_speed * 100.0 / self.iso).log2()
+    }
+
+    /// Exposure in stops that maps the luminance saturating the sensor at
+    /// these settings to 1. Scene radiance is converted to luminance with the
+    /// scale of the sky, so "sunny 16" exposes a sunlit scene near 0.
+    pub fn exposure(&self) -> f32 {
+        // Luminance in cd/m² of a radiance of 1
+        let unit_luminance = 1000.0 / LUMINANCE_SCALE;
+
+        (unit_luminance / SATURATION_LUMINANCE).log2() - self.ev100()
+    }
+}
+
+impl From<&config::PhysicalCamera> for Physical {
+    fn from(config: &config::PhysicalCamera) -> Self {
+        Self {
+            focal_length: config.focal_length,
+            sensor_width: config.sensor_width.unwrap_or(FULL_FRAME_SENSOR.0),
+            sensor_height: config.sensor_height.unwrap_or(FULL_FRAME_SENSOR.1),
+            f_stop: config.f_stop,
+            shutter_speed: config.shutter_speed.unwrap_or(1.0),
+            iso: config.iso.unwrap_or(100.0),
+            white_balance: config.white_balance.unwrap_or(DEFAULT_WHITE_BALANCE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::Sky;
+    use crate::math::Vector3;
+
+    fn camera(f_stop: Option<f32>, shutter_speed: f32, iso: f32) -> Physical {
+        Physical {
+            focal_length: 50.0,
+            sensor_width: 36.0,
+            sensor_height: 24.0,
+            f_stop,
+            shutter_speed,
+            iso,
+            white_balance: DEFAULT_WHITE_BALANCE,
+        }
+    }
+
+    #[test]
+    fn test_fov_crops_the_sensor() {
+        let camera = camera(None, 1.0, 100.0);
+        let full_height = 2.0 * (12.0_f32 / 50.0).atan();
+
+        // 3:2 matches the sensor, a wider image uses less of its height
+        assert_eq_within_bound!(camera.fov(1.5), full_height, 1e-5);
+        assert_eq_within_bound!(camera.fov(1.0), full_height, 1e-5);
+        assert_eq_within_bound!(camera.fov(3.0), 2.0 * (6.0_f32 / 50.0).atan(), 1e-5);
+    }
+
+    #[test]
+    fn test_exposure() {
+        assert_eq_within_bound!(camera(None, 1.0, 100.0).ev100(), 0.0, 1e-5);
+        assert_eq_within_bound!(camera(Some(2.0), 1.0, 100.0).ev100(), 2.0, 1e-5);
+        assert_eq_within_bound!(camera(Some(4.0), 0.5, 400.0).ev100(), 3.0, 1e-5);
+
+        // Every stop of exposure value takes a stop of exposure away
+        let reference = camera(None, 1.0, 100.0).exposure();
+        assert_eq_within_bound!(
+            camera(Some(4.0), 0.5, 400.0).exposure(),
+            reference - 3.0,
+            1e-4
+        );
+        assert_eq_within_bound!(
+            camera(Some(2.0), 1.0, 100.0).aperture().unwrap(),
+            0.025,
+            1e-6
+        );
+    }
+
+    #[test]
+    fn test_sunny_16() {
+        // f/16, 1/100 s and ISO 100 show an 18% grey card in the midday sun
+        // as middle grey
+        let sun = Sky::new(Vector3::new(0.0, 1.0, -0.3), 3.0)
+            .sun(1.0)
+            .unwrap();
+        let grey_card = sun.color.luminance() * sun.intensity(0.0) * 0.18;
+        let exposure = camera(Some(16.0), 0.01, 100.0).exposure();
+
+        assert!(exposure.abs() < 1.0, "Exposure of {} EV", exposure);
+        assert_eq_within_bound!(grey_card * 2.0_f32.powf(exposure), 0.18, 0.05);
+    }
+}
@@ -0,0 +1,192 @@
+use serde::Deserialize;
+
+use super::{Camera, Frame};
+use crate::math::{Point2, Point3, Vector3};
+use crate::ray::Ray;
+use crate::scheduler::Tile;
+
+/// How the two eyes of a stereo rig are arranged in the output.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StereoLayout {
+    /// One image per eye
+    Separate,
+    /// Left eye on the left, right eye on the right
+    SideBySide,
+    /// Left eye on top, right eye below
+    OverUnder,
+}
+
+/// The placement of one eye of a stereo rig relative to the center of the
+/// camera.
+#[derive(Debug, Copy, Clone)]
+pub struct Eye {
+    /// Distance from the center along the camera's right axis, negative for
+    /// the left eye
+    pub offset: f32,
+    /// Distance at which both eyes see the same point, objects there appear
+    /// at the depth of the screen. The eyes look in parallel without it.
+    pub convergence_distance: Option<f32>,
+}
+
+impl Eye {
+    /// Move a ray leaving the center of the rig along `direction` to this
+    /// eye. Returns the position of the eye and the new direction.
+    ///
+    /// With a convergence distance the ray is turned to pass through the
+    /// point the center ray reaches at `convergence_distance` times
+    /// `direction`. For planar projections `direction` has a z of -1 so the
+    /// rays meet on a plane, which shifts the frustum rather than toeing the
+    /// cameras in.
+    pub fn apply(&self, direction: Vector3, axis: Vector3) -> (Point3, Vector3) {
+        let offset = axis * self.offset;
+        let direction = match self.convergence_distance {
+            None => direction,
+            Some(distance) => direction * distance - offset,
+        };
+
+        (offset.as_point(), direction)
+    }
+}
+
+/// Renders the views of both eyes next to or above each other in one image.
+/// With `StereoLayout::Separate` the eyes are rendered side by side and split
+/// when saving.
+#[derive(Debug)]
+pub struct Stereo {
+    left: Box<dyn Camera>,
+    right: Box<dyn Camera>,
+    layout: StereoLayout,
+}
+
+impl Stereo {
+    pub fn new(left: Box<dyn Camera>, right: Box<dyn Camera>, layout: StereoLayout) -> Self {
+        debug_assert_eq!(
+            (left.width(), left.height()),
+            (right.width(), right.height())
+        );
+
+        Self {
+            left,
+            right,
+            layout,
+        }
+    }
+
+    pub fn layout(&self) -> StereoLayout {
+        self.layout
+    }
+
+    fn is_over_under(&self) -> bool {
+        self.layout == StereoLayout::OverUnder
+    }
+}
+
+impl Camera for Stereo {
+    fn frame(&self) -> &Frame {
+        self.left.frame()
+    }
+
+    fn width(&self) -> u32 {
+        match self.is_over_under() {
+            true => self.left.width(),
+            false => self.left.width() * 2,
+        }
+    }
+
+    fn height(&self) -> u32 {
+        match self.is_over_under() {
+            true => self.left.height() * 2,
+            false => self.left.height(),
+        }
+    }
+
+    fn create_ray(&self, x: u32, y: u32, offset: Point2, lens_sample: Point2) -> Option<Ray> {
+        let (width, height) = (self.left.width(), self.left.height());
+
+        // Film coordinates have y pointing up, the left eye is the top half
+        match self.is_over_under() {
+            true if y >= height => self.left.create_ray(x, y - height, offset, lens_sample),
+            true => self.right.create_ray(x, y, offset, lens_sample),
+            false if x < width => self.left.create_ray(x, y, offset, lens_sample),
+            false => self.right.create_ray(x - width, y, offset, lens_sample),
+        }
+    }
+
+    /// Both eyes share their viewing direction and only differ in position
+    /// along an axis perpendicular to it.
+    fn depth(&self, ray: &Ray, point: Point3) -> f32 {
+        self.left.depth(ray, point)
+    }
+
+    fn views(&self) -> Vec<Tile> {
+        let (width, height) = (self.left.width(), self.left.height());
+        let second = match self.is_over_under() {
+            true => (0, height),
+            false => (width, 0),
+        };
+
+        vec![
+            Tile {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            },
+            Tile {
+                x: second.0,
+                y: second.1,
+                width,
+                height,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::{Frame, Perspective};
+
+    fn eye(offset: f32) -> Box<dyn Camera> {
+        let mut frame = Frame::new(
+            40,
+            20,
+            Point3::at_origin(),
+            Point3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        frame.eye = Some(Eye {
+            offset,
+            convergence_distance: Some(10.0),
+        });
+
+        Box::new(Perspective::new(frame, 1.0))
+    }
+
+    #[test]
+    fn test_eyes_converge() {
+        let rig = Stereo::new(eye(-0.5), eye(0.5), StereoLayout::SideBySide);
+        let center = Point2::new(0.0, 0.0);
+        let left = rig.create_ray(20, 10, center, center).unwrap();
+        let right = rig.create_ray(60, 10, center, center).unwrap();
+
+        assert_eq_within_bound!(left.origin.x, -0.5, 1e-5);
+        assert_eq_within_bound!(right.origin.x, 0.5, 1e-5);
+
+        // Both rays through the center of their image meet at the
+        // convergence distance
+        let meet = |ray: &Ray| ray.origin.x + ray.direction.x * (10.0 / -ray.direction.z);
+        assert_eq_within_bound!(meet(&left), 0.0, 1e-4);
+        assert_eq_within_bound!(meet(&right), 0.0, 1e-4);
+    }
+
+    #[test]
+    fn test_views_tile_the_image() {
+        for layout in [StereoLayout::SideBySide, StereoLayout::OverUnder] {
+            let rig = Stereo::new(eye(-0.5), eye(0.5), layout);
+            let area: u32 = rig.views().iter().map(|v| v.width * v.height).sum();
+
+            assert_eq!(area, rig.width() * rig.height());
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::framebuffer::Framebuffer;
+use crate::math::hash_all;
+use crate::renderer::ProgressiveState;
+
+const MAGIC: &[u8; 4] = b"RTCP";
+const VERSION: u32 = 2;
+
+/// Settings that only control how long a render runs and where its output
+/// goes. Changing them doesn't invalidate a checkpoint.
+const RESUMABLE_KEYS: [&str; 2] = ["progressive", "checkpoint"];
+
+/// The state of an unfinished progressive render. The samplers are
+/// deterministic given their seed and the sample index, so the accumulated
+/// buffers and the number of samples taken so far are enough to continue the
+/// render exactly where it stopped.
+pub struct Checkpoint {
+    /// Hash of the config the render was started with, see `config_hash`
+    pub config_hash: u64,
+    pub state: ProgressiveState,
+}
+
+impl Checkpoint {
+    /// Write a checkpoint of `state` to `path`. The file is written next to
+    /// the destination first and then moved into place, a crash while saving
+    /// leaves the previous checkpoint intact.
+    pub fn save(path: &Path, config_hash: u64, state: &ProgressiveState) -> io::Result<()> {
+        let temporary_path = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temporary_path)?);
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&config_hash.to_le_bytes())?;
+        writer.write_all(&state.passes.to_le_bytes())?;
+        writer.write_all(&state.samples_per_pixel.to_le_bytes())?;
+        writer.write_all(&state.elapsed.as_secs_f64().to_le_bytes())?;
+        state.accumulation.write_to(&mut writer)?;
+        match &state.even_passes {
+            Some(even_passes) => {
+                writer.write_all(&[1])?;
+                even_passes.write_to(&mut writer)?;
+            }
+            None => writer.write_all(&[0])?,
+        }
+
+        writer.into_inner()?.sync_all()?;
+        fs::rename(temporary_path, path)
+    }
+
+    /// Load the checkpoint at `path` of a render of `width` × `height`
+    /// pixels with `aov_count` AOVs
+    pub fn load(path: &Path, width: u32, height: u32, aov_count: usize) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_u32(&mut reader)? != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a checkpoint written by this version of rusttracer",
+            ));
+        }
+
+        let config_hash = read_u64(&mut reader)?;
+        let passes = read_u32(&mut reader)?;
+        let samples_per_pixel = read_u32(&mut reader)?;
+        let elapsed = Duration::from_secs_f64(f64::from_bits(read_u64(&mut reader)?));
+        let accumulation = Framebuffer::read_from(&mut reader, width, height, aov_count)?;
+        let mut has_even_passes = [0];
+        reader.read_exact(&mut has_even_passes)?;
+        let even_passes = match has_even_passes[0] {
+            0 => None,
+            _ => Some(Framebuffer::read_from(&mut reader, width, height, 0)?),
+        };
+
+        Ok(Self {
+            config_hash,
+            state: ProgressiveState {
+                passes,
+                samples_per_pixel,
+                elapsed,
+                accumulation,
+                even_passes,
+            },
+        })
+    }
+}
+
+/// A hash of the contents of a config file that ignores formatting, the order
+/// of keys and the settings in `RESUMABLE_KEYS`, together with the indices of
+/// the scene and camera being rendered. Files referenced by the config, like
+/// meshes and textures, are not included.
+pub fn config_hash(contents: &str, scene: usize, camera: usize) -> serde_json::Result<u64> {
+    let mut config: serde_json::Value = serde_json::from_str(contents)?;
+    if let Some(object) = config.as_object_mut() {
+        for key in &RESUMABLE_KEYS {
+            object.remove(*key);
+        }
+    }
+
+    // Objects are sorted by key, the serialized form is canonical
+    let canonical = serde_json::to_vec(&config)?;
+    let mut words: Vec<u64> = canonical
+        .chunks(8)
+        .map(|chunk| {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        })
+        .collect();
+    words.extend([scene as u64, camera as u64]);
+
+    Ok(hash_all(&words) ^ canonical.len() as u64)
+}
+
+pub(crate) fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u32::from_le_bytes(bytes))
+}
+
+pub(crate) fn read_f32<R: Read>(reader: &mut R) -> io::Result<f32> {
+    read_u32(reader).map(f32::from_bits)
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::filter::Filter;
+    use crate::math::Point2;
+
+    #[test]
+    fn test_config_hash_ignores_formatting_and_run_settings() {
+        let config = r#"{"max_depth": 5, "cameras": [{"fov": 0.8}]}"#;
+        let reformatted = r#"{
+            "cameras": [{ "fov": 0.8 }],
+            "max_depth": 5,
+            "progressive": { "target_samples": 1024 }
+        }"#;
+        let changed = r#"{"max_depth": 6, "cameras": [{"fov": 0.8}]}"#;
+
+        assert_eq!(
+            config_hash(config, 0, 0).unwrap(),
+            config_hash(reformatted, 0, 0).unwrap()
+        );
+        assert_ne!(
+            config_hash(config, 0, 0).unwrap(),
+            config_hash(changed, 0, 0).unwrap()
+        );
+        assert_ne!(
+            config_hash(config, 0, 0).unwrap(),
+            config_hash(config, 0, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join("rusttracer_test_round_trip.checkpoint");
+        let mut accumulation = Framebuffer::new(3, 2, 1);
+        accumulation.add_sample(
+            Point2::new(1.5, 0.5),
+            Color::new(0.25, 2.0, 8.0),
+            &[Color::red()],
+            &Filter::default(),
+        );
+        let state = ProgressiveState {
+            passes: 3,
+            samples_per_pixel: 12,
+            elapsed: Duration::from_millis(1500),
+            accumulation,
+            even_passes: None,
+        };
+
+        Checkpoint::save(&path, 42, &state).unwrap();
+        let loaded = Checkpoint::load(&path, 3, 2, 1).unwrap();
+        let mismatched = Checkpoint::load(&path, 300, 200, 1);
+        fs::remove_file(&path).unwrap();
+
+        assert!(mismatched.is_err(), "The image size is checked");
+        assert_eq!(loaded.config_hash, 42);
+        assert_eq!(loaded.state.passes, 3);
+        assert_eq!(loaded.state.samples_per_pixel, 12);
+        assert_eq!(loaded.state.elapsed, Duration::from_millis(1500));
+        assert!(loaded.state.even_passes.is_none());
+        assert_eq!(
+            loaded.state.accumulation.pixel(1, 0),
+            Color::new(0.25, 2.0, 8.0)
+        );
+        assert_eq!(loaded.state.accumulation.aov(0, 1, 0), Color::red());
+        assert_eq!(loaded.state.accumulation.sample_count(1, 0), 1);
+    }
+}
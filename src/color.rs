@@ -1,67 +1,33 @@
 use std::fmt;
-use std::iter::Iterator;
-use std::ops::{Add, Mul, Sub};
+use std::ops::{Add, Div, Mul, Sub};
 
-#[derive(Debug, Copy, Clone)]
+/// Linear RGB radiance/reflectance. Channels are stored as unclamped `f32`s,
+/// values above 1.0 are perfectly valid and only get quantised when the final
+/// image is written with `to_rgb8`.
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Color {
-    r: u8,
-    g: u8,
-    b: u8,
-    next: NextColor,
-}
-
-#[derive(Debug, Copy, Clone)]
-pub enum NextColor {
-    Red,
-    Green,
-    Blue,
-    Done,
-}
-
-impl Iterator for Color {
-    type Item = u8;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.next {
-            NextColor::Red => {
-                self.next = NextColor::Green;
-                Some(self.r)
-            }
-            NextColor::Green => {
-                self.next = NextColor::Blue;
-                Some(self.g)
-            }
-            NextColor::Blue => {
-                self.next = NextColor::Done;
-                Some(self.b)
-            }
-            NextColor::Done => None,
-        }
-    }
+    r: f32,
+    g: f32,
+    b: f32,
 }
 
 impl Color {
-    pub fn new(r: u8, g: u8, b: u8) -> Color {
-        Color {
-            r,
-            g,
-            b,
-            next: NextColor::Red,
-        }
+    pub const fn new(r: f32, g: f32, b: f32) -> Color {
+        Color { r, g, b }
     }
 
-    pub fn new_f32(r: f32, g: f32, b: f32) -> Color {
+    pub fn new_u8(r: u8, g: u8, b: u8) -> Color {
         Color::new(
-            Color::clamp((r * 255.0) as i32),
-            Color::clamp((g * 255.0) as i32),
-            Color::clamp((b * 255.0) as i32),
+            f32::from(r) / 255.0,
+            f32::from(g) / 255.0,
+            f32::from(b) / 255.0,
         )
     }
 
     pub fn new_hsv(hue: f32, saturation: f32, value: f32) -> Color {
         let normalized_hue = hue % 360.0;
-        let normalized_saturation = saturation.min(1.0).max(0.0);
-        let normalized_value = value.min(1.0).max(0.0);
+        let normalized_saturation = saturation.clamp(0.0, 1.0);
+        let normalized_value = value.clamp(0.0, 1.0);
 
         let chroma = normalized_saturation * normalized_value;
         let hue_d = normalized_hue / 60.0;
@@ -78,50 +44,94 @@ impl Color {
         };
 
         let m = normalized_value - chroma;
-        Color::new_f32(color.0 + m, color.1 + m, color.2 + m)
+        Color::new(color.0 + m, color.1 + m, color.2 + m)
     }
 
-    #[inline(always)]
-    pub fn r(self) -> u8 {
-        self.r
+    /// The linear sRGB color of a black body at `temperature` Kelvin with a
+    /// luminance of 1. Uses Kim et al.'s fit of the Planckian locus, which
+    /// covers 1667K to 25000K.
+    pub fn from_temperature(temperature: f32) -> Color {
+        let t = temperature.clamp(1667.0, 25000.0);
+        let (t2, t3) = (t * t, t * t * t);
+
+        let x = if t <= 4000.0 {
+            -0.266_123_9e9 / t3 - 0.234_358_9e6 / t2 + 0.877_695_6e3 / t + 0.179_910
+        } else {
+            -3.025_846_9e9 / t3 + 2.107_038e6 / t2 + 0.222_634_7e3 / t + 0.240_390
+        };
+        let (x2, x3) = (x * x, x * x * x);
+        let y = if t <= 2222.0 {
+            -1.106_381_4 * x3 - 1.348_110_2 * x2 + 2.185_558_3 * x - 0.202_196_83
+        } else if t <= 4000.0 {
+            -0.954_947_6 * x3 - 1.374_185_9 * x2 + 2.091_37 * x - 0.167_488_67
+        } else {
+            3.081_758 * x3 - 5.873_387 * x2 + 3.751_13 * x - 0.370_014_83
+        };
+
+        let color = Color::from_xyy(x, y, 1.0);
+
+        color / color.luminance()
     }
 
-    #[inline(always)]
-    pub fn r_f32(self) -> f32 {
-        f32::from(self.r()) / 255.0
+    /// The linear sRGB color with CIE chromaticity `x`, `y` and luminance
+    /// `luminance`, colors outside of the sRGB gamut are clipped
+    pub fn from_xyy(x: f32, y: f32, luminance: f32) -> Color {
+        // xyY to XYZ to linear sRGB
+        let (big_x, big_y, big_z) = (x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+
+        Color::new(
+            3.240_454_2 * big_x - 1.537_138_5 * big_y - 0.498_531_4 * big_z,
+            -0.969_266 * big_x + 1.876_010_8 * big_y + 0.041_556 * big_z,
+            0.055_643_4 * big_x - 0.204_025_9 * big_y + 1.057_225_2 * big_z,
+        )
+        .map(|channel| channel.max(0.0))
     }
 
     #[inline(always)]
-    pub fn g(self) -> u8 {
-        self.g
+    pub fn r(self) -> f32 {
+        self.r
     }
 
     #[inline(always)]
-    pub fn g_f32(self) -> f32 {
-        f32::from(self.g()) / 255.0
+    pub fn g(self) -> f32 {
+        self.g
     }
 
     #[inline(always)]
-    pub fn b(self) -> u8 {
+    pub fn b(self) -> f32 {
         self.b
     }
 
-    #[inline(always)]
-    pub fn b_f32(self) -> f32 {
-        f32::from(self.b()) / 255.0
+    pub fn max_component(self) -> f32 {
+        self.r.max(self.g).max(self.b)
     }
 
-    #[inline(always)]
-    pub fn as_u32(self) -> u32 {
-        0xFF00_0000 & u32::from(self.r) & u32::from(self.g) << 8 & u32::from(self.b) << 16
+    pub fn average(self) -> f32 {
+        (self.r + self.g + self.b) / 3.0
     }
 
-    fn clamp(value: i32) -> u8 {
-        match value {
-            v if v < 0 => 0,
-            v if v > i32::from(u8::max_value()) => u8::max_value(),
-            v => v as u8,
-        }
+    /// Relative luminance using the Rec. 709 primaries
+    pub fn luminance(self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    pub fn is_black(self) -> bool {
+        self.r <= 0.0 && self.g <= 0.0 && self.b <= 0.0
+    }
+
+    /// Apply `f` to each channel
+    pub fn map<F>(self, f: F) -> Color
+    where
+        F: Fn(f32) -> f32,
+    {
+        Color::new(f(self.r), f(self.g), f(self.b))
+    }
+
+    /// Quantise to 8 bits per channel, clamping values outside of [0, 1].
+    pub fn to_rgb8(self) -> [u8; 3] {
+        let quantise = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        [quantise(self.r), quantise(self.g), quantise(self.b)]
     }
 }
 
@@ -131,21 +141,9 @@ impl fmt::Display for Color {
     }
 }
 
-impl PartialEq for Color {
-    fn eq(&self, other: &Color) -> bool {
-        self.r == other.r && self.g == other.g && self.b == other.b
-    }
-}
-
-impl Eq for Color {}
-
 impl From<[f32; 3]> for Color {
     fn from(values: [f32; 3]) -> Self {
-        Self::new(
-            Self::clamp((values[0] * 255.0) as i32),
-            Self::clamp((values[1] * 255.0) as i32),
-            Self::clamp((values[2] * 255.0) as i32),
-        )
+        Self::new(values[0], values[1], values[2])
     }
 }
 
@@ -155,9 +153,7 @@ impl Add for Color {
     type Output = Color;
 
     fn add(self, other: Color) -> Color {
-        let f = |f: fn(Color) -> u8| Color::clamp(i32::from(f(self)) + i32::from(f(other)));
-
-        Color::new(f(Color::r), f(Color::g), f(Color::b))
+        Color::new(self.r + other.r, self.g + other.g, self.b + other.b)
     }
 }
 
@@ -165,11 +161,7 @@ impl Sub for Color {
     type Output = Color;
 
     fn sub(self, other: Color) -> Color {
-        let r = Color::clamp(i32::from(self.r()) - i32::from(other.r()));
-        let g = Color::clamp(i32::from(self.g()) - i32::from(other.g()));
-        let b = Color::clamp(i32::from(self.b()) - i32::from(other.b()));
-
-        Color::new(r, g, b)
+        Color::new(self.r - other.r, self.g - other.g, self.b - other.b)
     }
 }
 
@@ -177,11 +169,7 @@ impl Mul for Color {
     type Output = Color;
 
     fn mul(self, other: Color) -> Color {
-        let r = self.r_f32() * other.r_f32();
-        let g = self.g_f32() * other.g_f32();
-        let b = self.b_f32() * other.b_f32();
-
-        Color::new_f32(r, g, b)
+        Color::new(self.r * other.r, self.g * other.g, self.b * other.b)
     }
 }
 
@@ -189,11 +177,15 @@ impl Mul<f32> for Color {
     type Output = Color;
 
     fn mul(self, other: f32) -> Color {
-        let r = self.r_f32() * other;
-        let g = self.g_f32() * other;
-        let b = self.b_f32() * other;
+        Color::new(self.r * other, self.g * other, self.b * other)
+    }
+}
+
+impl Div<f32> for Color {
+    type Output = Color;
 
-        Color::new_f32(r, g, b)
+    fn div(self, other: f32) -> Color {
+        Color::new(self.r / other, self.g / other, self.b / other)
     }
 }
 
@@ -208,9 +200,62 @@ macro_rules! define_color {
 }
 
 impl Color {
-    define_color!(black, 0, 0, 0);
-    define_color!(white, 0xFF, 0xFF, 0xFF);
-    define_color!(red, 0xFF, 0, 0);
-    define_color!(green, 0, 0xFF, 0);
-    define_color!(blue, 0, 0, 0xFF);
+    define_color!(black, 0.0, 0.0, 0.0);
+    define_color!(white, 1.0, 1.0, 1.0);
+    define_color!(red, 1.0, 0.0, 0.0);
+    define_color!(green, 0.0, 1.0, 0.0);
+    define_color!(blue, 0.0, 0.0, 1.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Color;
+
+    #[test]
+    fn test_arithmetic_is_unclamped() {
+        let bright = Color::white() * 100.0 + Color::new(0.5, 0.0, 0.0);
+
+        assert_eq!(bright, Color::new(100.5, 100.0, 100.0));
+        assert_eq!(
+            Color::black() - Color::white(),
+            Color::new(-1.0, -1.0, -1.0)
+        );
+        assert_eq!(bright / 100.0 * Color::red(), Color::new(1.005, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_small_contributions_are_preserved() {
+        let mut result = Color::black();
+        for _ in 0..1000 {
+            result = result + Color::white() * 0.0001;
+        }
+
+        assert_eq_within_bound!(result.r(), 0.1, 1e-4);
+    }
+
+    #[test]
+    fn test_new_u8() {
+        assert_eq!(Color::new_u8(255, 0, 51), Color::new(1.0, 0.0, 0.2));
+    }
+
+    #[test]
+    fn test_from_temperature() {
+        let d65 = Color::from_temperature(6504.0);
+        assert_eq_within_bound!(d65.r(), 1.0, 0.05);
+        assert_eq_within_bound!(d65.g(), 1.0, 0.05);
+        assert_eq_within_bound!(d65.b(), 1.0, 0.05);
+
+        let candle = Color::from_temperature(1900.0);
+        assert!(candle.r() > candle.g() && candle.g() > candle.b());
+        assert_eq_within_bound!(candle.luminance(), 1.0, 1e-5);
+
+        let sky = Color::from_temperature(12000.0);
+        assert!(sky.b() > sky.r());
+    }
+
+    #[test]
+    fn test_to_rgb8() {
+        assert_eq!(Color::new(0.5, 1.0, 0.0).to_rgb8(), [128, 255, 0]);
+        assert_eq!(Color::new(12.0, -3.0, 0.2).to_rgb8(), [255, 0, 51]);
+    }
 }
@@ -0,0 +1,33 @@
+use serde::Deserialize;
+
+use crate::animation::{Interpolation, Target, Value};
+
+#[derive(Deserialize, Debug)]
+pub struct Frames {
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Keyframe {
+    pub frame: f32,
+    pub value: Value,
+    /// How to get from this keyframe to the next one, defaults to `Linear`
+    pub interpolation: Option<Interpolation>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Track {
+    #[serde(flatten)]
+    pub target: Target,
+    pub keyframes: Vec<Keyframe>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Animation {
+    pub frames: Frames,
+    /// Frames per second, defaults to 24. Physical cameras without a
+    /// `shutter` keep it open for their shutter speed at this rate.
+    pub fps: Option<f32>,
+    pub tracks: Vec<Track>,
+}
@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+use crate::aov::{Aov, AovFormat};
+
+#[derive(Deserialize, Debug)]
+pub struct Aovs {
+    pub outputs: Vec<Aov>,
+    pub format: Option<AovFormat>,
+}
@@ -1,13 +1,117 @@
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer};
 
-#[derive(Deserialize, Debug)]
+use crate::camera::{FisheyeMapping, StereoLayout};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum ApertureShape {
+    Circle,
+    Polygon { blades: u32, rotation: Option<f32> },
+    Image { path: String },
+}
+
+/// Cameras without a `type` are perspective cameras, like before other
+/// projections existed.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", remote = "Self")]
+pub enum Projection {
+    Perspective {
+        fov: f32,
+        /// Diameter of the lens in scene units, takes precedence over
+        /// `f_stop`
+        aperture: Option<f32>,
+        f_stop: Option<f32>,
+        /// Height of the sensor in scene units, used with `fov` to derive the
+        /// focal length for `f_stop`. Defaults to 0.024, a full frame sensor
+        /// when scene units are metres.
+        sensor_height: Option<f32>,
+        focus_distance: Option<f32>,
+        focus_on: Option<[f32; 3]>,
+        aperture_shape: Option<ApertureShape>,
+    },
+    Orthographic {
+        view_width: f32,
+    },
+    Equirectangular,
+    Fisheye {
+        fov: f32,
+        mapping: Option<FisheyeMapping>,
+    },
+    Physical(PhysicalCamera),
+}
+
+impl<'de> Deserialize<'de> for Projection {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+        if let Some(fields) = value.as_object_mut() {
+            fields.entry("type").or_insert_with(|| "Perspective".into());
+        }
+
+        Projection::deserialize(value).map_err(de::Error::custom)
+    }
+}
+
+/// A perspective camera described by the settings of a real one, lengths on
+/// the camera are in millimetres and scene units are metres.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PhysicalCamera {
+    pub focal_length: f32,
+    pub sensor_width: Option<f32>,
+    pub sensor_height: Option<f32>,
+    pub f_stop: Option<f32>,
+    /// Exposure time in seconds
+    pub shutter_speed: Option<f32>,
+    pub iso: Option<f32>,
+    /// Colour temperature in Kelvin that appears white
+    pub white_balance: Option<f32>,
+    pub focus_distance: Option<f32>,
+    pub focus_on: Option<[f32; 3]>,
+    pub aperture_shape: Option<ApertureShape>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Stereo {
+    pub interocular_distance: f32,
+    pub convergence_distance: Option<f32>,
+    pub layout: Option<StereoLayout>,
+}
+
+/// Place the camera so that `target` fills its image
+#[derive(Deserialize, Debug, Clone)]
+pub struct Framing {
+    /// `"all"` or the name of an object
+    pub target: String,
+    /// The direction the camera looks in, from `position` towards `look_at`
+    /// unless set
+    pub direction: Option<[f32; 3]>,
+    /// Fraction of the image left empty around the target
+    pub padding: Option<f32>,
+}
+
+/// When the shutter opens and closes, 0 is the start of the frame and 1 the
+/// start of the next one
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct Shutter {
+    pub open: f32,
+    pub close: f32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
 pub struct Camera {
-    pub fov: f32,
+    pub name: Option<String>,
     pub width: u32,
     pub height: u32,
     pub position: [f32; 3],
     pub look_at: [f32; 3],
     pub up: [f32; 3],
+    #[serde(flatten)]
+    pub projection: Projection,
+    pub stereo: Option<Stereo>,
+    pub shutter: Option<Shutter>,
+    pub frame: Option<Framing>,
 }
 
 impl Camera {
@@ -20,12 +124,24 @@ impl Camera {
         up: [f32; 3],
     ) -> Camera {
         Camera {
-            fov,
+            name: None,
             width,
             height,
             position,
             look_at,
             up,
+            projection: Projection::Perspective {
+                fov,
+                aperture: None,
+                f_stop: None,
+                sensor_height: None,
+                focus_distance: None,
+                focus_on: None,
+                aperture_shape: None,
+            },
+            stereo: None,
+            shutter: None,
+            frame: None,
         }
     }
 }
@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct Checkpoint {
+    pub path: Option<String>,
+    pub interval_seconds: Option<f32>,
+}
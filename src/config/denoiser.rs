@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct Denoiser {
+    pub iterations: Option<u32>,
+    pub color_sigma: Option<f32>,
+    pub normal_sigma: Option<f32>,
+    pub albedo_sigma: Option<f32>,
+    pub depth_sigma: Option<f32>,
+}
@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+/// Pixel reconstruction filter, see `crate::filter::Filter`. Omitted
+/// parameters use sensible per filter defaults.
+#[derive(Deserialize, Debug, Copy, Clone)]
+#[serde(tag = "type")]
+pub enum Filter {
+    Box {
+        radius: Option<f32>,
+    },
+    Tent {
+        radius: Option<f32>,
+    },
+    Gaussian {
+        radius: Option<f32>,
+        alpha: Option<f32>,
+    },
+    Mitchell {
+        radius: Option<f32>,
+        b: Option<f32>,
+        c: Option<f32>,
+    },
+    Lanczos {
+        radius: Option<f32>,
+    },
+}
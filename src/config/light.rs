@@ -1,9 +1,10 @@
 use serde::Deserialize;
 
-use crate::light::Falloff;
+use crate::light::{AreaShape, Falloff};
 
 #[derive(Deserialize, Debug)]
 #[serde(tag = "type")]
+#[allow(clippy::enum_variant_names)]
 pub enum Light {
     PointLight {
         origin: [f32; 3],
@@ -20,4 +21,33 @@ pub enum Light {
         specular: Option<bool>,
         diffuse: Option<bool>,
     },
+    SpotLight {
+        origin: [f32; 3],
+        direction: [f32; 3],
+        /// Angle from `direction` to the edge of the fully lit cone, in
+        /// radians
+        inner_angle: f32,
+        /// Angle from `direction` to the edge of the lit cone, in radians
+        outer_angle: f32,
+        falloff_exponent: Option<f32>,
+        color: [f32; 3],
+        intensity: f32,
+        falloff: Option<Falloff>,
+        specular: Option<bool>,
+        diffuse: Option<bool>,
+    },
+    AreaLight {
+        origin: [f32; 3],
+        /// The direction rectangles and disks face, down unless set
+        direction: Option<[f32; 3]>,
+        /// The height of rectangles runs along `up`
+        up: Option<[f32; 3]>,
+        shape: AreaShape,
+        color: [f32; 3],
+        intensity: f32,
+        /// Shadow rays per shaded point
+        samples: Option<u32>,
+        specular: Option<bool>,
+        diffuse: Option<bool>,
+    },
 }
@@ -23,4 +23,9 @@ pub struct Material {
     pub illumination_model: IllumninationModel,
     pub reflection_coefficient: Option<f32>,
     pub refraction_coefficient: Option<f32>,
+    pub emission_color: Option<[f32; 3]>,
+    pub emission_texture: OptionalTexture,
+    pub emission_strength: Option<f32>,
+    pub dissolve: Option<f32>,
+    pub dissolve_texture: OptionalTexture,
 }
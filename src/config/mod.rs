@@ -1,18 +1,36 @@
+mod animation;
+mod aovs;
 mod camera;
+mod checkpoint;
+mod denoiser;
+mod filter;
 mod light;
 mod material;
 mod object;
+mod progressive;
+mod sampler;
 mod scene;
+mod tiles;
+mod tone_mapping;
 mod transform;
 
 use serde::Deserialize;
 
-pub use self::camera::Camera;
+pub use self::animation::{Animation, Track};
+pub use self::aovs::Aovs;
+pub use self::camera::{ApertureShape, Camera, Framing, PhysicalCamera, Projection, Shutter};
+pub use self::checkpoint::Checkpoint;
+pub use self::denoiser::Denoiser;
+pub use self::filter::Filter;
 pub use self::light::Light;
 pub use self::material::Material;
 pub use self::material::Texture;
-pub use self::object::Object;
+pub use self::object::{MotionKey, Object};
+pub use self::progressive::Progressive;
+pub use self::sampler::Sampler;
 pub use self::scene::Scene;
+pub use self::tiles::Tiles;
+pub use self::tone_mapping::ToneMapping;
 pub use self::transform::Transform;
 
 use std::error::Error;
@@ -21,7 +39,7 @@ use std::fs::File;
 use std::io;
 use std::io::Read;
 
-use crate::renderer::SuperSampling;
+use crate::renderer::{Integrator, SuperSampling};
 
 #[derive(Debug)]
 pub struct ConfigError {
@@ -30,7 +48,7 @@ pub struct ConfigError {
 }
 
 impl ConfigError {
-    fn new(message: String, cause: Option<Box<dyn Error>>) -> Self {
+    pub(crate) fn new(message: String, cause: Option<Box<dyn Error>>) -> Self {
         ConfigError { message, cause }
     }
 }
@@ -75,6 +93,16 @@ impl From<serde_json::error::Error> for ConfigError {
 pub struct Config {
     pub max_depth: u32,
     pub super_sampling: SuperSampling,
+    pub sampler: Option<Sampler>,
+    pub filter: Option<Filter>,
+    pub integrator: Option<Integrator>,
+    pub tone_mapping: Option<ToneMapping>,
+    pub aovs: Option<Aovs>,
+    pub denoiser: Option<Denoiser>,
+    pub tiles: Option<Tiles>,
+    pub progressive: Option<Progressive>,
+    pub checkpoint: Option<Checkpoint>,
+    pub animation: Option<Animation>,
     pub cameras: Vec<Camera>,
     pub scenes: Vec<Scene>,
     pub materials: Vec<Material>,
@@ -104,4 +132,40 @@ impl Config {
 
         Ok(config)
     }
+
+    /// The scene called `name` and its index, the first scene when `name` is
+    /// `None`
+    pub fn scene(&self, name: Option<&str>) -> Result<(usize, &Scene), ConfigError> {
+        find_named(&self.scenes, name, "scene", |scene| scene.name.as_deref())
+    }
+
+    /// The camera called `name` and its index, the first camera when `name`
+    /// is `None`
+    pub fn camera(&self, name: Option<&str>) -> Result<(usize, &Camera), ConfigError> {
+        find_named(&self.cameras, name, "camera", |camera| {
+            camera.name.as_deref()
+        })
+    }
+}
+
+fn find_named<'a, T>(
+    items: &'a [T],
+    name: Option<&str>,
+    kind: &str,
+    name_of: impl Fn(&T) -> Option<&str>,
+) -> Result<(usize, &'a T), ConfigError> {
+    let Some(name) = name else {
+        return items.first().map(|item| (0, item)).ok_or_else(|| {
+            ConfigError::new(
+                format!("Config should provide at least one {}, found none", kind),
+                None,
+            )
+        });
+    };
+
+    items
+        .iter()
+        .enumerate()
+        .find(|(_, item)| name_of(item) == Some(name))
+        .ok_or_else(|| ConfigError::new(format!("Config has no {} named {}", kind, name), None))
 }
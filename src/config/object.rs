@@ -2,27 +2,55 @@ use serde::Deserialize;
 
 use super::Transform;
 
+/// The transforms of a moving object at `time`, 0 is the start of the frame
+/// and 1 the start of the next one. All keys list the same types of transforms
+/// in the same order.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MotionKey {
+    pub time: f32,
+    pub transforms: Vec<Transform>,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(tag = "type")]
 pub enum Object {
     Sphere {
+        name: Option<String>,
         radius: f32,
         transforms: Option<Vec<Transform>>,
+        motion: Option<Vec<MotionKey>>,
         material_name: Option<String>,
     },
     Plane {
+        name: Option<String>,
         normal: [f32; 3],
         transforms: Option<Vec<Transform>>,
+        motion: Option<Vec<MotionKey>>,
         material_name: Option<String>,
     },
     Mesh {
+        name: Option<String>,
         path: String,
         transforms: Option<Vec<Transform>>,
+        motion: Option<Vec<MotionKey>>,
         material_name: Option<String>,
     },
     MeshInstance {
+        name: Option<String>,
         path: String,
         transforms: Option<Vec<Transform>>,
+        motion: Option<Vec<MotionKey>>,
         material_name: Option<String>,
     },
 }
+
+impl Object {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Object::Sphere { name, .. }
+            | Object::Plane { name, .. }
+            | Object::Mesh { name, .. }
+            | Object::MeshInstance { name, .. } => name.as_deref(),
+        }
+    }
+}
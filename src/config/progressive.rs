@@ -0,0 +1,11 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct Progressive {
+    pub samples_per_pass: Option<u32>,
+    pub target_samples: Option<u32>,
+    pub time_limit_seconds: Option<f32>,
+    pub noise_threshold: Option<f32>,
+    pub update_every_passes: Option<u32>,
+    pub update_every_seconds: Option<f32>,
+}
@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+/// Strategy used to place samples within pixels, see `crate::sampler`. Defaults
+/// to `Grid`, or `Sobol` with adaptive super sampling.
+#[derive(Deserialize, Debug, Copy, Clone)]
+#[serde(tag = "type")]
+pub enum Sampler {
+    /// Regular grid, one sample in the center of each cell. Lens and time
+    /// samples are jittered.
+    Grid,
+    Uniform {
+        seed: Option<u64>,
+    },
+    Stratified {
+        seed: Option<u64>,
+    },
+    Halton {
+        seed: Option<u64>,
+    },
+    Sobol {
+        seed: Option<u64>,
+    },
+    BlueNoise {
+        seed: Option<u64>,
+    },
+}
@@ -1,12 +1,48 @@
 use serde::Deserialize;
 
+use crate::light::SunPosition;
+
 use super::light::Light;
 use super::object::Object;
 
 #[derive(Deserialize, Debug)]
 pub struct Scene {
+    pub name: Option<String>,
     pub clear_color: [f32; 3],
     pub ambient_color: [f32; 3],
     pub objects: Vec<Object>,
     pub lights: Vec<Light>,
+    pub environment: Option<Environment>,
+    pub sky: Option<Sky>,
+}
+
+/// An equirectangular image surrounding the scene that lights it
+#[derive(Deserialize, Debug)]
+pub struct Environment {
+    /// Relative to the config file, HDR and EXR images hold linear radiance
+    pub path: String,
+    /// Counter clockwise around the y axis, in radians
+    pub rotation: Option<f32>,
+    pub intensity: Option<f32>,
+    /// Whether camera rays that miss everything see the environment instead
+    /// of the clear color
+    pub background: Option<bool>,
+    /// Shadow rays towards the environment per shaded point
+    pub samples: Option<u32>,
+}
+
+/// An analytic daylight sky and the sun, in place of an environment image
+#[derive(Deserialize, Debug)]
+pub struct Sky {
+    pub sun: SunPosition,
+    /// Haziness of the atmosphere, from 2 for a very clear sky to 10 for a
+    /// hazy one
+    pub turbidity: Option<f32>,
+    /// Scales both the sky and the sun
+    pub intensity: Option<f32>,
+    /// Whether camera rays that miss everything see the sky instead of the
+    /// clear color
+    pub background: Option<bool>,
+    /// Shadow rays towards the sky per shaded point
+    pub samples: Option<u32>,
 }
@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+use crate::scheduler::TileOrder;
+
+#[derive(Deserialize, Debug)]
+pub struct Tiles {
+    pub size: Option<u32>,
+    pub order: Option<TileOrder>,
+}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+use crate::tone_mapping::{Operator, TransferFunction};
+
+#[derive(Deserialize, Debug)]
+pub struct ToneMapping {
+    pub exposure: Option<f32>,
+    pub operator: Option<Operator>,
+    pub transfer_function: Option<TransferFunction>,
+}
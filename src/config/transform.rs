@@ -3,7 +3,7 @@ use serde::Deserialize;
 use crate::geometry::Transformable;
 use crate::math;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 #[serde(tag = "type")]
 pub enum Transform {
     Translate { value: [f32; 3] },
@@ -28,6 +28,41 @@ impl Transform {
         }
     }
 
+    /// The transform a fraction `t` of the way from this one to `other`, `None`
+    /// when they are of different types
+    pub fn lerp(&self, other: &Transform, t: f32) -> Option<Transform> {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let lerp3 =
+            |a: [f32; 3], b: [f32; 3]| [lerp(a[0], b[0]), lerp(a[1], b[1]), lerp(a[2], b[2])];
+
+        Some(match (self, other) {
+            (Transform::Translate { value: a }, Transform::Translate { value: b }) => {
+                Transform::Translate {
+                    value: lerp3(*a, *b),
+                }
+            }
+            (Transform::Scale { value: a }, Transform::Scale { value: b }) => Transform::Scale {
+                value: lerp3(*a, *b),
+            },
+            (Transform::RotateX { value: a }, Transform::RotateX { value: b }) => {
+                Transform::RotateX {
+                    value: lerp(*a, *b),
+                }
+            }
+            (Transform::RotateY { value: a }, Transform::RotateY { value: b }) => {
+                Transform::RotateY {
+                    value: lerp(*a, *b),
+                }
+            }
+            (Transform::RotateZ { value: a }, Transform::RotateZ { value: b }) => {
+                Transform::RotateZ {
+                    value: lerp(*a, *b),
+                }
+            }
+            _ => return None,
+        })
+    }
+
     pub fn perform(&self, transformable: &mut dyn Transformable) {
         let transform = self.to_transform();
 
@@ -1,19 +1,28 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use crate::camera;
 use crate::color::Color;
 use crate::config;
+use crate::denoiser::Denoiser;
+use crate::filter::Filter;
+use crate::geometry::{ExtentVolume, Octree};
 use crate::material;
 use crate::mesh_loader::MeshLoader;
 use crate::renderer;
+use crate::sampler;
 use crate::scene;
+use crate::scheduler::Schedule;
 use crate::texture;
+use crate::tone_mapping::ToneMapping;
 
 pub struct ConfigLoader {
     fallback_material: Rc<material::Material>,
     named_textures: HashMap<String, Rc<dyn texture::Texture>>,
+    /// Kept between loads so that the meshes of animations are only read
+    /// once, keyed by the directory the config's paths are relative to
+    mesh_loaders: HashMap<PathBuf, MeshLoader<ExtentVolume, Octree>>,
 }
 
 impl ConfigLoader {
@@ -21,6 +30,7 @@ impl ConfigLoader {
         Self {
             fallback_material,
             named_textures: HashMap::default(),
+            mesh_loaders: HashMap::default(),
         }
     }
 
@@ -28,54 +38,219 @@ impl ConfigLoader {
         self.named_textures.insert(name.to_string(), texture);
     }
 
+    /// Build a renderer for the scene and camera called `scene` and `camera`,
+    /// or the first of each when not given
     pub fn load_renderer_from_config(
-        &self,
+        &mut self,
         path: &str,
+        scene: Option<&str>,
+        camera: Option<&str>,
     ) -> Result<(renderer::Renderer, config::Config), Box<dyn std::error::Error>> {
         let parsed_config = config::Config::new_from_file(path)?;
-        let scene_path = Path::new(path).parent().unwrap();
-        let mut mesh_loader = MeshLoader::new(scene_path.to_path_buf());
+        let renderer = self.load_renderer(
+            &parsed_config,
+            Path::new(path).parent().unwrap(),
+            scene,
+            camera,
+        )?;
 
+        Ok((renderer, parsed_config))
+    }
+
+    /// Like `load_renderer_from_config` for a config that has already been
+    /// parsed, paths in it are relative to `scene_path`
+    pub fn load_renderer(
+        &mut self,
+        parsed_config: &config::Config,
+        scene_path: &Path,
+        scene: Option<&str>,
+        camera: Option<&str>,
+    ) -> Result<renderer::Renderer, Box<dyn std::error::Error>> {
         let materials = parsed_config
             .materials
             .iter()
             .map(|material_config| {
                 // TODO: Error handling
-                (
-                    material_config.name.to_owned(),
-                    Rc::new(material::Material::new_with_textures(
-                        Color::from(material_config.ambient_color),
-                        self.resolve_texture(&material_config.ambient_texture)
-                            .unwrap(),
-                        Color::from(material_config.diffuse_color),
-                        self.resolve_texture(&material_config.diffuse_texture)
-                            .unwrap(),
-                        Color::from(material_config.specular_color),
-                        self.resolve_texture(&material_config.specular_texture)
-                            .unwrap(),
-                        material_config.specular_exponent,
-                        material_config.illumination_model,
-                        material_config.reflection_coefficient,
-                        material_config.refraction_coefficient,
-                    )),
-                )
+                let mut material = material::Material::new_with_textures(
+                    Color::from(material_config.ambient_color),
+                    self.resolve_texture(&material_config.ambient_texture)
+                        .unwrap(),
+                    Color::from(material_config.diffuse_color),
+                    self.resolve_texture(&material_config.diffuse_texture)
+                        .unwrap(),
+                    Color::from(material_config.specular_color),
+                    self.resolve_texture(&material_config.specular_texture)
+                        .unwrap(),
+                    material_config.specular_exponent,
+                    material_config.illumination_model,
+                    material_config.reflection_coefficient,
+                    material_config.refraction_coefficient,
+                );
+                material.emission_texture = self
+                    .resolve_texture(&material_config.emission_texture)
+                    .unwrap();
+                // An emission texture on its own emits its colors as they are
+                let default_emission = if material.emission_texture.is_some() {
+                    Color::white()
+                } else {
+                    Color::black()
+                };
+                material.emission_color = material_config
+                    .emission_color
+                    .map_or(default_emission, Color::from);
+                material.emission_strength = material_config.emission_strength.unwrap_or(1.0);
+                material.dissolve = material_config.dissolve.unwrap_or(1.0);
+                material.dissolve_texture = self
+                    .resolve_texture(&material_config.dissolve_texture)
+                    .unwrap();
+
+                (material_config.name.to_owned(), Rc::new(material))
             })
             .collect();
 
+        let (_, scene_config) = parsed_config.scene(scene)?;
+        let mesh_loader = self
+            .mesh_loaders
+            .entry(scene_path.to_path_buf())
+            .or_insert_with(|| MeshLoader::new(scene_path.to_path_buf()));
         let scene = scene::Scene::new_from_config(
-            parsed_config.scenes.first().unwrap(),
+            scene_config,
             &materials,
-            &mut mesh_loader,
+            mesh_loader,
             Rc::clone(&self.fallback_material),
         )?;
-        let camera_config = parsed_config
-            .cameras
-            .first()
-            .expect("Config should contain at least one valid camera");
-        let camera = camera::Camera::from(camera_config);
-        let renderer = renderer::Renderer::new(scene, camera, parsed_config.super_sampling);
+        let (_, camera_config) = parsed_config.camera(camera)?;
+        let camera = Self::build_camera(camera_config, &scene, scene_path)?;
+        let tone_mapping = Self::build_tone_mapping(parsed_config, camera_config);
+        let settings = renderer::RenderSettings {
+            super_sampling: parsed_config.super_sampling,
+            sampler: Self::build_sampler(parsed_config.sampler, parsed_config.super_sampling)?,
+            filter: parsed_config
+                .filter
+                .as_ref()
+                .map(Filter::from)
+                .unwrap_or_default(),
+            integrator: parsed_config
+                .integrator
+                .unwrap_or(renderer::Integrator::Whitted),
+            tone_mapping,
+            aovs: parsed_config
+                .aovs
+                .as_ref()
+                .map(|aovs| aovs.outputs.clone())
+                .unwrap_or_default(),
+            denoiser: parsed_config.denoiser.as_ref().map(Denoiser::from),
+            schedule: parsed_config
+                .tiles
+                .as_ref()
+                .map(Schedule::from)
+                .unwrap_or_default(),
+        };
+        let renderer = renderer::Renderer::new(scene, camera, settings);
 
-        Ok((renderer, parsed_config))
+        Ok(renderer)
+    }
+
+    /// Render through the camera described by `camera_config` from now on
+    pub fn set_camera(
+        &self,
+        renderer: &mut renderer::Renderer,
+        parsed_config: &config::Config,
+        camera_config: &config::Camera,
+        scene_path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let camera = Self::build_camera(camera_config, renderer.scene(), scene_path)?;
+        renderer.set_camera(camera);
+        renderer.set_tone_mapping(Self::build_tone_mapping(parsed_config, camera_config));
+
+        Ok(())
+    }
+
+    fn build_camera(
+        camera_config: &config::Camera,
+        scene: &scene::Scene,
+        scene_path: &Path,
+    ) -> Result<Box<dyn camera::Camera>, Box<dyn std::error::Error>> {
+        let Some(framing) = &camera_config.frame else {
+            return Ok(camera::from_config(camera_config, scene_path)?);
+        };
+
+        let target = Some(framing.target.as_str()).filter(|target| *target != "all");
+        let bounds = scene.bounds(target).ok_or_else(|| {
+            config::ConfigError::new(
+                format!(
+                    "Can't frame {}, the scene has no bounded objects by that name",
+                    framing.target
+                ),
+                None,
+            )
+        })?;
+        let mut framed = camera_config.clone();
+        camera::frame(&mut framed, framing, &bounds)?;
+
+        Ok(camera::from_config(&framed, scene_path)?)
+    }
+
+    /// Physical cameras add their exposure and white balance to the
+    /// configured tone mapping
+    fn build_tone_mapping(
+        parsed_config: &config::Config,
+        camera_config: &config::Camera,
+    ) -> ToneMapping {
+        let tone_mapping = parsed_config
+            .tone_mapping
+            .as_ref()
+            .map(ToneMapping::from)
+            .unwrap_or_default();
+        let config::Projection::Physical(physical) = &camera_config.projection else {
+            return tone_mapping;
+        };
+        let physical = camera::Physical::from(physical);
+
+        ToneMapping {
+            exposure: tone_mapping.exposure + physical.exposure(),
+            ..tone_mapping
+        }
+        .with_white_balance(physical.white_balance)
+    }
+
+    fn build_sampler(
+        config: Option<config::Sampler>,
+        super_sampling: renderer::SuperSampling,
+    ) -> Result<Box<dyn sampler::Sampler>, config::ConfigError> {
+        let samples_per_pixel = super_sampling.max_samples_per_pixel();
+        // Adaptive sampling can stop after any number of samples, only
+        // sequences that are well distributed at every prefix cover the pixel
+        let adaptive = matches!(super_sampling, renderer::SuperSampling::Adaptive { .. });
+        let config = match config {
+            None if adaptive => config::Sampler::Sobol { seed: None },
+            None => config::Sampler::Grid,
+            Some(config::Sampler::Grid | config::Sampler::Stratified { .. }) if adaptive => {
+                return Err(config::ConfigError::new(
+                    String::from(
+                        "Adaptive super sampling needs a progressive sampler like Sobol or \
+                         Halton, grid and stratified samples only cover the whole pixel \
+                         once all of them are taken",
+                    ),
+                    None,
+                ));
+            }
+            Some(config) => config,
+        };
+
+        Ok(match config {
+            config::Sampler::Grid => Box::new(sampler::Grid::new(samples_per_pixel)),
+            config::Sampler::Uniform { seed } => Box::new(sampler::Uniform::new(seed.unwrap_or(0))),
+            config::Sampler::Stratified { seed } => Box::new(sampler::Stratified::new(
+                seed.unwrap_or(0),
+                samples_per_pixel,
+            )),
+            config::Sampler::Halton { seed } => Box::new(sampler::Halton::new(seed.unwrap_or(0))),
+            config::Sampler::Sobol { seed } => Box::new(sampler::Sobol::new(seed.unwrap_or(0))),
+            config::Sampler::BlueNoise { seed } => {
+                Box::new(sampler::BlueNoise::new(seed.unwrap_or(0)))
+            }
+        })
     }
 
     fn resolve_texture(
@@ -94,3 +269,67 @@ impl ConfigLoader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::Solid;
+
+    #[test]
+    fn test_emission_texture_without_color() {
+        let config: config::Config = serde_json::from_str(
+            r#"{
+                "max_depth": 1,
+                "super_sampling": "Off",
+                "cameras": [{
+                    "fov": 0.8,
+                    "width": 4,
+                    "height": 4,
+                    "position": [0, 0, 0],
+                    "look_at": [0, 0, -1],
+                    "up": [0, 1, 0]
+                }],
+                "scenes": [{
+                    "clear_color": [0, 0, 0],
+                    "ambient_color": [0, 0, 0],
+                    "objects": [{
+                        "type": "Sphere",
+                        "radius": 1,
+                        "material_name": "glowing",
+                        "transforms": [{ "type": "Translate", "value": [0, 0, -5] }]
+                    }],
+                    "lights": []
+                }],
+                "materials": [{
+                    "name": "glowing",
+                    "ambient_color": [0, 0, 0],
+                    "diffuse_color": [0, 0, 0],
+                    "specular_color": [0, 0, 0],
+                    "specular_exponent": 0,
+                    "illumination_model": "Diffuse",
+                    "emission_texture": { "name": "glow" }
+                }]
+            }"#,
+        )
+        .unwrap();
+        let fallback_material = material::Material::new(
+            Color::black(),
+            Color::white(),
+            Color::black(),
+            0.0,
+            material::IllumninationModel::Diffuse,
+            None,
+            None,
+        );
+        let mut loader = ConfigLoader::new(Rc::new(fallback_material));
+        loader.register_named_texture("glow", Rc::new(Solid::new(Color::new(0.5, 0.25, 1.0))));
+
+        let renderer = loader
+            .load_renderer(&config, Path::new("."), None, None)
+            .unwrap();
+
+        // The sphere glows with the colors of the texture
+        assert!(renderer.scene().is_light(0));
+        assert_eq!(renderer.scene().lights.len(), 1);
+    }
+}
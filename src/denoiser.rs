@@ -0,0 +1,260 @@
+use rayon::prelude::*;
+
+use crate::color::Color;
+use crate::config;
+
+/// B3 spline used as the base kernel of the à-trous transform
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Guards against dividing by black albedo when demodulating
+const MIN_ALBEDO: f32 = 1e-3;
+
+/// Per pixel features of the primary hits used to find edges that the filter
+/// must not blur across.
+pub struct Features<'a> {
+    pub albedo: &'a [Color],
+    pub normal: &'a [Color],
+    /// Camera space depth, stored in the red channel
+    pub depth: &'a [Color],
+}
+
+/// Edge-avoiding à-trous wavelet filter, see Dammertz et al. 2010
+/// "Edge-Avoiding À-Trous Wavelet Transform for fast Global Illumination
+/// Filtering".
+///
+/// The colour is divided by the albedo before filtering so that texture
+/// detail survives, only the lighting is smoothed.
+#[derive(Debug, Copy, Clone)]
+pub struct Denoiser {
+    /// Number of filter passes, the footprint doubles with each pass
+    pub iterations: u32,
+    /// Tolerance for differences in colour, halved every iteration
+    pub color_sigma: f32,
+    pub normal_sigma: f32,
+    pub albedo_sigma: f32,
+    /// Tolerance for relative differences in depth
+    pub depth_sigma: f32,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            color_sigma: 0.6,
+            normal_sigma: 0.1,
+            albedo_sigma: 0.1,
+            depth_sigma: 0.05,
+        }
+    }
+}
+
+impl Denoiser {
+    pub fn denoise(
+        &self,
+        width: u32,
+        height: u32,
+        color: &[Color],
+        features: &Features,
+    ) -> Vec<Color> {
+        let (width, height) = (width as usize, height as usize);
+        debug_assert_eq!(color.len(), width * height);
+
+        let albedo: Vec<Color> = features
+            .albedo
+            .iter()
+            .map(|albedo| albedo.map(|channel| if channel < MIN_ALBEDO { 1.0 } else { channel }))
+            .collect();
+        let mut irradiance: Vec<Color> = color
+            .iter()
+            .zip(&albedo)
+            .map(|(color, albedo)| {
+                Color::new(
+                    color.r() / albedo.r(),
+                    color.g() / albedo.g(),
+                    color.b() / albedo.b(),
+                )
+            })
+            .collect();
+
+        for iteration in 0..self.iterations {
+            let step = 1_i64 << iteration;
+            let color_sigma = self.color_sigma * 0.5_f32.powi(iteration as i32);
+
+            irradiance = (0..width * height)
+                .into_par_iter()
+                .map(|index| {
+                    self.filter_pixel(
+                        index,
+                        (width, height),
+                        step,
+                        color_sigma,
+                        &irradiance,
+                        features,
+                    )
+                })
+                .collect();
+        }
+
+        irradiance
+            .into_iter()
+            .zip(albedo)
+            .map(|(irradiance, albedo)| irradiance * albedo)
+            .collect()
+    }
+
+    fn filter_pixel(
+        &self,
+        index: usize,
+        (width, height): (usize, usize),
+        step: i64,
+        color_sigma: f32,
+        irradiance: &[Color],
+        features: &Features,
+    ) -> Color {
+        let (x, y) = ((index % width) as i64, (index / width) as i64);
+        let center_color = irradiance[index];
+        let center_normal = features.normal[index];
+        let center_albedo = features.albedo[index];
+        let center_depth = features.depth[index].r();
+
+        let mut sum = Color::black();
+        let mut total_weight = 0.0;
+
+        for (ky, kernel_y) in KERNEL.iter().enumerate() {
+            let sample_y = y + (ky as i64 - 2) * step;
+            if sample_y < 0 || sample_y >= height as i64 {
+                continue;
+            }
+
+            for (kx, kernel_x) in KERNEL.iter().enumerate() {
+                let sample_x = x + (kx as i64 - 2) * step;
+                if sample_x < 0 || sample_x >= width as i64 {
+                    continue;
+                }
+
+                let sample = (sample_y as usize) * width + sample_x as usize;
+                let color = irradiance[sample];
+                let depth_difference = (features.depth[sample].r() - center_depth).abs()
+                    / center_depth.abs().max(f32::EPSILON);
+
+                let weight = kernel_x
+                    * kernel_y
+                    * edge_stop(distance_squared(color, center_color), color_sigma)
+                    * edge_stop(
+                        distance_squared(features.normal[sample], center_normal),
+                        self.normal_sigma,
+                    )
+                    * edge_stop(
+                        distance_squared(features.albedo[sample], center_albedo),
+                        self.albedo_sigma,
+                    )
+                    * edge_stop(depth_difference * depth_difference, self.depth_sigma);
+
+                sum = sum + color * weight;
+                total_weight += weight;
+            }
+        }
+
+        // The center pixel always has a weight of at least 9/64
+        sum / total_weight
+    }
+}
+
+fn distance_squared(a: Color, b: Color) -> f32 {
+    let difference = a - b;
+
+    difference.r() * difference.r()
+        + difference.g() * difference.g()
+        + difference.b() * difference.b()
+}
+
+fn edge_stop(distance_squared: f32, sigma: f32) -> f32 {
+    (-distance_squared / (sigma * sigma).max(f32::EPSILON)).exp()
+}
+
+impl From<&config::Denoiser> for Denoiser {
+    fn from(config: &config::Denoiser) -> Self {
+        let defaults = Self::default();
+
+        Self {
+            iterations: config.iterations.unwrap_or(defaults.iterations),
+            color_sigma: config.color_sigma.unwrap_or(defaults.color_sigma),
+            normal_sigma: config.normal_sigma.unwrap_or(defaults.normal_sigma),
+            albedo_sigma: config.albedo_sigma.unwrap_or(defaults.albedo_sigma),
+            depth_sigma: config.depth_sigma.unwrap_or(defaults.depth_sigma),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Rng;
+
+    const SIZE: u32 = 32;
+
+    fn variance(values: &[Color]) -> f32 {
+        let mean = values.iter().map(|c| c.r()).sum::<f32>() / values.len() as f32;
+
+        values
+            .iter()
+            .map(|c| (c.r() - mean) * (c.r() - mean))
+            .sum::<f32>()
+            / values.len() as f32
+    }
+
+    #[test]
+    fn test_removes_noise_from_flat_regions() {
+        let mut rng = Rng::new(4);
+        let pixels = (SIZE * SIZE) as usize;
+        let noisy: Vec<Color> = (0..pixels)
+            .map(|_| Color::white() * (0.3 + 0.2 * rng.next_f32()))
+            .collect();
+        let albedo = vec![Color::white(); pixels];
+        let normal = vec![Color::new(0.0, 1.0, 0.0); pixels];
+        let depth = vec![Color::white() * 10.0; pixels];
+        let features = Features {
+            albedo: &albedo,
+            normal: &normal,
+            depth: &depth,
+        };
+
+        let denoised = Denoiser::default().denoise(SIZE, SIZE, &noisy, &features);
+
+        assert!(variance(&denoised) < variance(&noisy) * 0.1);
+    }
+
+    #[test]
+    fn test_preserves_geometric_edges() {
+        let pixels = (SIZE * SIZE) as usize;
+        let left = |index: usize| (index as u32 % SIZE) < SIZE / 2;
+        let color: Vec<Color> = (0..pixels)
+            .map(|i| if left(i) { Color::red() } else { Color::blue() })
+            .collect();
+        let normal: Vec<Color> = (0..pixels)
+            .map(|i| {
+                if left(i) {
+                    Color::new(1.0, 0.0, 0.0)
+                } else {
+                    Color::new(0.0, 0.0, 1.0)
+                }
+            })
+            .collect();
+        let albedo = vec![Color::white(); pixels];
+        let depth = vec![Color::white() * 10.0; pixels];
+        let features = Features {
+            albedo: &albedo,
+            normal: &normal,
+            depth: &depth,
+        };
+
+        let denoised = Denoiser::default().denoise(SIZE, SIZE, &color, &features);
+
+        for (i, value) in denoised.iter().enumerate() {
+            let expected = color[i];
+
+            assert_eq_within_bound!(value.r(), expected.r(), 1e-3);
+            assert_eq_within_bound!(value.b(), expected.b(), 1e-3);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::f32::consts::PI;
+
+use crate::config;
+
+/// Pixel reconstruction filters. Each sample contributes to every pixel whose
+/// center lies within `radius` pixels of it, weighted by the filter.
+///
+/// All filters are separable, the 2D weight is the product of the 1D weights
+/// along x and y.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Filter {
+    /// Constant weight, with a radius of 0.5 each sample only contributes to
+    /// the pixel it was taken in.
+    Box { radius: f32 },
+    /// Linear falloff from the center.
+    Tent { radius: f32 },
+    /// Gaussian with falloff `alpha`, shifted to reach zero at `radius`.
+    Gaussian { radius: f32, alpha: f32 },
+    /// Mitchell–Netravali cubic, see "Reconstruction Filters in Computer
+    /// Graphics" (1988).
+    Mitchell { radius: f32, b: f32, c: f32 },
+    /// Windowed sinc with `radius` lobes.
+    Lanczos { radius: f32 },
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::Box { radius: 0.5 }
+    }
+}
+
+impl Filter {
+    pub fn radius(&self) -> f32 {
+        match *self {
+            Filter::Box { radius }
+            | Filter::Tent { radius }
+            | Filter::Gaussian { radius, .. }
+            | Filter::Mitchell { radius, .. }
+            | Filter::Lanczos { radius } => radius,
+        }
+    }
+
+    /// Weight of a sample at offset (`dx`, `dy`) from a pixel center.
+    pub fn evaluate(&self, dx: f32, dy: f32) -> f32 {
+        self.evaluate_1d(dx) * self.evaluate_1d(dy)
+    }
+
+    fn evaluate_1d(&self, offset: f32) -> f32 {
+        let x = offset.abs();
+        let radius = self.radius();
+
+        if x > radius {
+            return 0.0;
+        }
+
+        match *self {
+            Filter::Box { .. } => 1.0,
+            Filter::Tent { .. } => radius - x,
+            Filter::Gaussian { alpha, .. } => {
+                ((-alpha * x * x).exp() - (-alpha * radius * radius).exp()).max(0.0)
+            }
+            Filter::Mitchell { b, c, .. } => mitchell(2.0 * x / radius, b, c),
+            Filter::Lanczos { .. } => sinc(x) * sinc(x / radius),
+        }
+    }
+}
+
+/// The Mitchell–Netravali cubic for `x` in [0, 2]
+fn mitchell(x: f32, b: f32, c: f32) -> f32 {
+    let weight = if x > 1.0 {
+        (-b - 6.0 * c) * x * x * x
+            + (6.0 * b + 30.0 * c) * x * x
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c)
+    } else {
+        (12.0 - 9.0 * b - 6.0 * c) * x * x * x
+            + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+            + (6.0 - 2.0 * b)
+    };
+
+    weight / 6.0
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-5 {
+        return 1.0;
+    }
+
+    (PI * x).sin() / (PI * x)
+}
+
+impl From<&config::Filter> for Filter {
+    fn from(config: &config::Filter) -> Self {
+        match *config {
+            config::Filter::Box { radius } => Filter::Box {
+                radius: radius.unwrap_or(0.5),
+            },
+            config::Filter::Tent { radius } => Filter::Tent {
+                radius: radius.unwrap_or(1.0),
+            },
+            config::Filter::Gaussian { radius, alpha } => Filter::Gaussian {
+                radius: radius.unwrap_or(1.5),
+                alpha: alpha.unwrap_or(2.0),
+            },
+            config::Filter::Mitchell { radius, b, c } => Filter::Mitchell {
+                radius: radius.unwrap_or(2.0),
+                b: b.unwrap_or(1.0 / 3.0),
+                c: c.unwrap_or(1.0 / 3.0),
+            },
+            config::Filter::Lanczos { radius } => Filter::Lanczos {
+                radius: radius.unwrap_or(3.0),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn all_filters() -> Vec<Filter> {
+        vec![
+            Filter::Box { radius: 0.5 },
+            Filter::Tent { radius: 1.0 },
+            Filter::Gaussian {
+                radius: 1.5,
+                alpha: 2.0,
+            },
+            Filter::Mitchell {
+                radius: 2.0,
+                b: 1.0 / 3.0,
+                c: 1.0 / 3.0,
+            },
+            Filter::Lanczos { radius: 3.0 },
+        ]
+    }
+
+    #[test]
+    fn test_filters_vanish_outside_radius() {
+        for filter in all_filters() {
+            let outside = filter.radius() + 0.01;
+
+            assert_eq!(filter.evaluate(outside, 0.0), 0.0, "{:?}", filter);
+            assert_eq!(filter.evaluate(0.0, -outside), 0.0, "{:?}", filter);
+            assert!(filter.evaluate(0.0, 0.0) > 0.0, "{:?}", filter);
+        }
+    }
+
+    #[test]
+    fn test_filters_peak_at_center() {
+        for filter in all_filters() {
+            let center = filter.evaluate(0.0, 0.0);
+
+            for i in 1..20 {
+                let offset = filter.radius() * i as f32 / 20.0;
+
+                assert!(filter.evaluate(offset, 0.0) <= center, "{:?}", filter);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mitchell_has_negative_lobes() {
+        let filter = Filter::Mitchell {
+            radius: 2.0,
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        };
+
+        assert!(filter.evaluate(1.5, 0.0) < 0.0);
+        assert_eq_within_bound!(filter.evaluate(2.0, 0.0), 0.0, EPSILON);
+    }
+
+    #[test]
+    fn test_lanczos_zero_crossings() {
+        let filter = Filter::Lanczos { radius: 3.0 };
+
+        assert_eq_within_bound!(filter.evaluate(1.0, 0.0), 0.0, EPSILON);
+        assert_eq_within_bound!(filter.evaluate(0.0, 2.0), 0.0, EPSILON);
+    }
+}
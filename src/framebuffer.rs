@@ -0,0 +1,482 @@
+use std::io::{self, Read, Write};
+
+use crate::checkpoint::{read_f32, read_u32};
+use crate::color::Color;
+use crate::filter::Filter;
+use crate::math::Point2;
+
+#[derive(Debug, Copy, Clone)]
+struct Pixel {
+    weighted_sum: Color,
+    weight: f32,
+    /// Number of samples taken within the pixel
+    samples: u32,
+    /// Squared distance from the pixel's center to the sample the nearest
+    /// AOVs were taken from
+    nearest_distance: f32,
+}
+
+impl Pixel {
+    const EMPTY: Pixel = Pixel {
+        weighted_sum: Color::new(0.0, 0.0, 0.0),
+        weight: 0.0,
+        samples: 0,
+        nearest_distance: f32::INFINITY,
+    };
+}
+
+/// How the samples of an AOV are combined into pixels
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AovFilter {
+    /// Filtered with the same weights as the beauty pass
+    Filtered,
+    /// The value of the sample within the pixel closest to its center, for
+    /// values like IDs that mustn't be blended
+    Nearest,
+}
+
+/// Accumulates filtered radiance samples. A framebuffer covers a rectangular
+/// region of the image, renders are split into regions that are filled in
+/// parallel and then merged into the framebuffer for the full image.
+///
+/// Positions are in continuous image coordinates, the pixel (x, y) covers
+/// [x, x + 1) × [y, y + 1) with y pointing down.
+///
+/// Besides the beauty pass each pixel holds `aov_count` arbitrary output
+/// variables. They are filtered with the same weights as the beauty pass so
+/// that they line up exactly when composited, unless they are set to be taken
+/// from the nearest sample with `with_aov_filters`.
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    pixels: Vec<Pixel>,
+    aov_count: usize,
+    aov_filters: Vec<AovFilter>,
+    aov_sums: Vec<Color>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32, aov_count: usize) -> Self {
+        Self::new_region(0, 0, width, height, aov_count)
+    }
+
+    /// A framebuffer for the `width` × `height` pixels with the top left
+    /// corner at (`x`, `y`).
+    pub fn new_region(x: u32, y: u32, width: u32, height: u32, aov_count: usize) -> Self {
+        let pixel_count = width as usize * height as usize;
+
+        Self {
+            x,
+            y,
+            width,
+            height,
+            pixels: vec![Pixel::EMPTY; pixel_count],
+            aov_count,
+            aov_filters: vec![AovFilter::Filtered; aov_count],
+            aov_sums: vec![Color::black(); pixel_count * aov_count],
+        }
+    }
+
+    /// Set how each AOV is combined into pixels, there has to be one filter
+    /// per AOV
+    pub fn with_aov_filters(mut self, filters: &[AovFilter]) -> Self {
+        debug_assert_eq!(filters.len(), self.aov_count);
+        self.aov_filters = filters.to_vec();
+
+        self
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Splat `color` and the values of the AOVs onto every pixel within the
+    /// filter's radius of `position`. Pixels outside of this framebuffer are
+    /// skipped.
+    pub fn add_sample(&mut self, position: Point2, color: Color, aovs: &[Color], filter: &Filter) {
+        debug_assert_eq!(aovs.len(), self.aov_count);
+
+        let (pixel_x, pixel_y) = (position.x.floor() as i64, position.y.floor() as i64);
+        if self.contains(pixel_x, pixel_y) {
+            let index = self.index(pixel_x as u32, pixel_y as u32);
+            let pixel = &mut self.pixels[index];
+            pixel.samples += 1;
+
+            let (dx, dy) = (position.x.fract() - 0.5, position.y.fract() - 0.5);
+            let distance = dx * dx + dy * dy;
+            if distance < pixel.nearest_distance {
+                pixel.nearest_distance = distance;
+                self.set_nearest_aovs(index, aovs);
+            }
+        }
+
+        let radius = filter.radius();
+        // Pixel centers are at half integer coordinates
+        let (center_x, center_y) = (position.x - 0.5, position.y - 0.5);
+
+        let min_x = ((center_x - radius).ceil() as i64).max(i64::from(self.x));
+        let max_x = ((center_x + radius).floor() as i64).min(i64::from(self.x + self.width) - 1);
+        let min_y = ((center_y - radius).ceil() as i64).max(i64::from(self.y));
+        let max_y = ((center_y + radius).floor() as i64).min(i64::from(self.y + self.height) - 1);
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let weight = filter.evaluate(x as f32 - center_x, y as f32 - center_y);
+                if weight == 0.0 {
+                    continue;
+                }
+
+                let index = self.index(x as u32, y as u32);
+                let pixel = &mut self.pixels[index];
+                pixel.weighted_sum = pixel.weighted_sum + color * weight;
+                pixel.weight += weight;
+
+                let first_aov = index * self.aov_count;
+                for ((sum, value), filter) in self.aov_sums[first_aov..first_aov + self.aov_count]
+                    .iter_mut()
+                    .zip(aovs)
+                    .zip(&self.aov_filters)
+                {
+                    if *filter == AovFilter::Filtered {
+                        *sum = *sum + *value * weight;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Add the accumulated samples of `other` to the overlapping pixels of
+    /// this framebuffer.
+    pub fn merge(&mut self, other: &Framebuffer) {
+        let min_x = self.x.max(other.x);
+        let max_x = (self.x + self.width).min(other.x + other.width);
+        let min_y = self.y.max(other.y);
+        let max_y = (self.y + self.height).min(other.y + other.height);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let source = other.pixels[other.index(x, y)];
+                let index = self.index(x, y);
+                let pixel = &mut self.pixels[index];
+
+                pixel.weighted_sum = pixel.weighted_sum + source.weighted_sum;
+                pixel.weight += source.weight;
+                pixel.samples += source.samples;
+                let nearer = source.nearest_distance < pixel.nearest_distance;
+                if nearer {
+                    pixel.nearest_distance = source.nearest_distance;
+                }
+
+                let source_aovs = other.index(x, y) * other.aov_count;
+                for aov in 0..self.aov_count.min(other.aov_count) {
+                    let sum = &mut self.aov_sums[index * self.aov_count + aov];
+                    let value = other.aov_sums[source_aovs + aov];
+                    match self.aov_filters[aov] {
+                        AovFilter::Filtered => *sum = *sum + value,
+                        AovFilter::Nearest if nearer => *sum = value,
+                        AovFilter::Nearest => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// The reconstructed value of the pixel at (`x`, `y`) in image
+    /// coordinates.
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let pixel = self.pixels[self.index(x, y)];
+
+        // Filters with negative lobes can in rare cases cancel out completely
+        if pixel.weight <= 0.0 {
+            return Color::black();
+        }
+
+        pixel.weighted_sum / pixel.weight
+    }
+
+    /// The reconstructed value of the `aov`-th output variable at (`x`, `y`)
+    pub fn aov(&self, aov: usize, x: u32, y: u32) -> Color {
+        let index = self.index(x, y);
+        if self.aov_filters[aov] == AovFilter::Nearest {
+            return self.aov_sums[index * self.aov_count + aov];
+        }
+        let weight = self.pixels[index].weight;
+
+        if weight <= 0.0 {
+            return Color::black();
+        }
+
+        self.aov_sums[index * self.aov_count + aov] / weight
+    }
+
+    /// The number of samples taken within the pixel at (`x`, `y`)
+    pub fn sample_count(&self, x: u32, y: u32) -> u32 {
+        self.pixels[self.index(x, y)].samples
+    }
+
+    /// Grayscale 8 bit image of the sample counts, normalised so that the
+    /// most sampled pixel is white.
+    pub fn sample_count_image(&self) -> Vec<u8> {
+        let max_samples = self
+            .pixels
+            .iter()
+            .map(|pixel| pixel.samples)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        self.pixels
+            .iter()
+            .map(|pixel| (pixel.samples as f32 / max_samples as f32 * 255.0).round() as u8)
+            .collect()
+    }
+
+    /// Serialize the accumulated sums, the inverse of `read_from`.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for value in [
+            self.x,
+            self.y,
+            self.width,
+            self.height,
+            self.aov_count as u32,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+
+        for filter in &self.aov_filters {
+            writer.write_all(&[*filter as u8])?;
+        }
+
+        for pixel in &self.pixels {
+            write_color(writer, pixel.weighted_sum)?;
+            writer.write_all(&pixel.weight.to_le_bytes())?;
+            writer.write_all(&pixel.samples.to_le_bytes())?;
+            writer.write_all(&pixel.nearest_distance.to_le_bytes())?;
+        }
+
+        for sum in &self.aov_sums {
+            write_color(writer, *sum)?;
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize a framebuffer written by `write_to`. It has to cover the
+    /// full `width` × `height` image and have at most `max_aov_count` AOVs,
+    /// anything else is rejected before allocating the buffers.
+    pub fn read_from<R: Read>(
+        reader: &mut R,
+        width: u32,
+        height: u32,
+        max_aov_count: usize,
+    ) -> io::Result<Self> {
+        let (x, y) = (read_u32(reader)?, read_u32(reader)?);
+        let size = (read_u32(reader)?, read_u32(reader)?);
+        let aov_count = read_u32(reader)? as usize;
+        if (x, y) != (0, 0) || size != (width, height) || aov_count > max_aov_count {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Expected a {}x{} framebuffer with up to {} AOVs, found {}x{} at ({}, {}) \
+                     with {}",
+                    width, height, max_aov_count, size.0, size.1, x, y, aov_count
+                ),
+            ));
+        }
+        (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(aov_count.max(1)))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Framebuffer too large"))?;
+        let mut framebuffer = Self::new_region(x, y, width, height, aov_count);
+
+        for filter in &mut framebuffer.aov_filters {
+            let mut byte = [0];
+            reader.read_exact(&mut byte)?;
+            *filter = match byte[0] {
+                0 => AovFilter::Filtered,
+                _ => AovFilter::Nearest,
+            };
+        }
+
+        for pixel in &mut framebuffer.pixels {
+            pixel.weighted_sum = read_color(reader)?;
+            pixel.weight = read_f32(reader)?;
+            pixel.samples = read_u32(reader)?;
+            pixel.nearest_distance = read_f32(reader)?;
+        }
+
+        for sum in &mut framebuffer.aov_sums {
+            *sum = read_color(reader)?;
+        }
+
+        Ok(framebuffer)
+    }
+
+    fn set_nearest_aovs(&mut self, index: usize, aovs: &[Color]) {
+        let first_aov = index * self.aov_count;
+        for ((value, sample), filter) in self.aov_sums[first_aov..first_aov + self.aov_count]
+            .iter_mut()
+            .zip(aovs)
+            .zip(&self.aov_filters)
+        {
+            if *filter == AovFilter::Nearest {
+                *value = *sample;
+            }
+        }
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        x >= i64::from(self.x)
+            && x < i64::from(self.x + self.width)
+            && y >= i64::from(self.y)
+            && y < i64::from(self.y + self.height)
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        debug_assert!(x >= self.x && x < self.x + self.width);
+        debug_assert!(y >= self.y && y < self.y + self.height);
+
+        ((y - self.y) * self.width + (x - self.x)) as usize
+    }
+}
+
+fn write_color<W: Write>(writer: &mut W, color: Color) -> io::Result<()> {
+    for channel in [color.r(), color.g(), color.b()] {
+        writer.write_all(&channel.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn read_color<R: Read>(reader: &mut R) -> io::Result<Color> {
+    Ok(Color::new(
+        read_f32(reader)?,
+        read_f32(reader)?,
+        read_f32(reader)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    #[test]
+    fn test_box_filter_averages_samples_within_pixel() {
+        let filter = Filter::Box { radius: 0.5 };
+        let mut framebuffer = Framebuffer::new(2, 2, 0);
+
+        framebuffer.add_sample(Point2::new(1.25, 0.25), Color::white(), &[], &filter);
+        framebuffer.add_sample(Point2::new(1.75, 0.75), Color::black(), &[], &filter);
+
+        assert_eq_within_bound!(framebuffer.pixel(1, 0).r(), 0.5, EPSILON);
+        assert_eq!(framebuffer.pixel(0, 0), Color::black());
+        assert_eq!(framebuffer.pixel(1, 1), Color::black());
+        assert_eq!(framebuffer.sample_count(1, 0), 2);
+        assert_eq!(framebuffer.sample_count(1, 1), 0);
+    }
+
+    #[test]
+    fn test_wide_filters_splat_into_neighbours() {
+        let filter = Filter::Tent { radius: 1.5 };
+        let mut framebuffer = Framebuffer::new(3, 3, 0);
+
+        framebuffer.add_sample(Point2::new(1.5, 1.5), Color::red(), &[], &filter);
+
+        for y in 0..3 {
+            for x in 0..3 {
+                assert_eq!(framebuffer.pixel(x, y), Color::red());
+            }
+        }
+        assert_eq!(framebuffer.sample_count(1, 1), 1);
+        assert_eq!(framebuffer.sample_count(0, 1), 0);
+    }
+
+    #[test]
+    fn test_nearest_aovs_are_not_blended() {
+        let filter = Filter::Tent { radius: 1.5 };
+        let mut framebuffer =
+            Framebuffer::new(2, 1, 2).with_aov_filters(&[AovFilter::Filtered, AovFilter::Nearest]);
+        let mut region = Framebuffer::new_region(1, 0, 1, 1, 2)
+            .with_aov_filters(&[AovFilter::Filtered, AovFilter::Nearest]);
+
+        framebuffer.add_sample(
+            Point2::new(0.1, 0.5),
+            Color::white(),
+            &[Color::red(); 2],
+            &filter,
+        );
+        framebuffer.add_sample(
+            Point2::new(0.6, 0.4),
+            Color::white(),
+            &[Color::blue(); 2],
+            &filter,
+        );
+        region.add_sample(
+            Point2::new(1.5, 0.5),
+            Color::white(),
+            &[Color::green(); 2],
+            &filter,
+        );
+        framebuffer.merge(&region);
+
+        assert_eq!(framebuffer.aov(1, 0, 0), Color::blue());
+        assert_eq!(framebuffer.aov(1, 1, 0), Color::green());
+        // Filtered AOVs still blend in samples from the neighbouring pixel
+        assert!(framebuffer.aov(0, 1, 0).r() > 0.0);
+    }
+
+    #[test]
+    fn test_merging_regions_matches_single_framebuffer() {
+        let filter = Filter::Gaussian {
+            radius: 1.5,
+            alpha: 2.0,
+        };
+        let samples = [
+            (Point2::new(0.3, 0.9), Color::new(1.0, 0.5, 0.0)),
+            (Point2::new(1.9, 1.1), Color::new(0.2, 0.1, 3.0)),
+            (Point2::new(2.5, 3.7), Color::new(0.0, 2.0, 0.4)),
+        ];
+        let mut expected = Framebuffer::new(4, 4, 1);
+        let mut merged = Framebuffer::new(4, 4, 1);
+        let mut top = Framebuffer::new_region(0, 0, 4, 3, 1);
+        let mut bottom = Framebuffer::new_region(0, 2, 4, 2, 1);
+
+        for &(position, color) in &samples {
+            expected.add_sample(position, color, &[color * 2.0], &filter);
+
+            // Samples are only taken in the rows a region owns but are
+            // splatted into the whole region including its margin
+            if position.y < 2.0 {
+                top.add_sample(position, color, &[color * 2.0], &filter);
+            } else {
+                bottom.add_sample(position, color, &[color * 2.0], &filter);
+            }
+        }
+        merged.merge(&top);
+        merged.merge(&bottom);
+
+        for y in 0..4 {
+            for x in 0..4 {
+                let (a, b) = (expected.pixel(x, y), merged.pixel(x, y));
+
+                assert_eq_within_bound!(a.r(), b.r(), EPSILON);
+                assert_eq_within_bound!(a.g(), b.g(), EPSILON);
+                assert_eq_within_bound!(a.b(), b.b(), EPSILON);
+
+                let aov = merged.aov(0, x, y);
+                assert_eq_within_bound!(aov.r(), 2.0 * a.r(), EPSILON);
+                assert_eq_within_bound!(aov.g(), 2.0 * a.g(), EPSILON);
+                assert_eq_within_bound!(aov.b(), 2.0 * a.b(), EPSILON);
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use super::{BoundingVolume, Triangle};
-use crate::math::{Point3, Vector3};
+use crate::math::{Matrix4, Point3, Vector3};
 use crate::ray::Ray;
 
 #[derive(Debug, Clone)]
@@ -70,6 +70,46 @@ impl AABB {
         (self.min() + self.half().as_vector()).as_point()
     }
 
+    pub fn corners(&self) -> [Point3; 8] {
+        let (min, max) = (self.min(), self.max());
+
+        std::array::from_fn(|corner| {
+            Point3::new(
+                if corner & 1 == 0 { min.x } else { max.x },
+                if corner & 2 == 0 { min.y } else { max.y },
+                if corner & 4 == 0 { min.z } else { max.z },
+            )
+        })
+    }
+
+    /// The smallest box containing both boxes
+    pub fn union(&self, other: &AABB) -> AABB {
+        let (min, max) = (other.min(), other.max());
+
+        self.expand(min).expand(max)
+    }
+
+    /// The smallest box containing this box and `point`
+    pub fn expand(&self, point: Point3) -> AABB {
+        let (min, max) = (self.min(), self.max());
+
+        Self::new(
+            Point3::new(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z)),
+            Point3::new(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z)),
+        )
+    }
+
+    /// The bounds of this box after transforming it with `matrix`
+    pub fn transformed(&self, matrix: &Matrix4) -> AABB {
+        let corners = self.corners().map(|corner| *matrix * corner);
+
+        corners[1..]
+            .iter()
+            .fold(Self::new(corners[0], corners[0]), |bounds, corner| {
+                bounds.expand(*corner)
+            })
+    }
+
     pub fn intersects_triangle_aabb(&self, triangle: &Triangle) -> bool {
         let bounding_box = Self::from_triangle(triangle);
 
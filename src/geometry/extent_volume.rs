@@ -26,7 +26,7 @@ pub struct ExtentVolume {
 
 impl BoundingVolume for ExtentVolume {
     fn from_triangles(triangles: &mut dyn Iterator<Item = &Triangle>) -> Self {
-        let mut distances = [[std::f32::INFINITY, std::f32::NEG_INFINITY]; NUM_PLANE_SET_NORMALS];
+        let mut distances = [[f32::INFINITY, f32::NEG_INFINITY]; NUM_PLANE_SET_NORMALS];
 
         for triangle in triangles {
             for point in &triangle.vertices {
@@ -47,8 +47,8 @@ impl BoundingVolume for ExtentVolume {
     fn intersect(&self, ray: Ray) -> bool {
         let (precomputed_numerator, precomputed_denominator) = Self::precompute(&ray);
 
-        let mut t_near = std::f32::NEG_INFINITY;
-        let mut t_far = std::f32::INFINITY;
+        let mut t_near = f32::NEG_INFINITY;
+        let mut t_far = f32::INFINITY;
 
         for i in 0..NUM_PLANE_SET_NORMALS {
             let mut tn =
@@ -77,9 +77,9 @@ impl ExtentVolume {
         let origin_vector = ray.origin.as_vector();
 
         let mut precomputed_numerator: [f32; NUM_PLANE_SET_NORMALS] =
-            [std::f32::NAN; NUM_PLANE_SET_NORMALS];
+            [f32::NAN; NUM_PLANE_SET_NORMALS];
         let mut precomputed_denominator: [f32; NUM_PLANE_SET_NORMALS] =
-            [std::f32::NAN; NUM_PLANE_SET_NORMALS];
+            [f32::NAN; NUM_PLANE_SET_NORMALS];
 
         for i in 0..NUM_PLANE_SET_NORMALS {
             precomputed_numerator[i] = PLAN_SET_NORMALS[i].dot(&origin_vector);
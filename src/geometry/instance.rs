@@ -1,8 +1,12 @@
 use std::rc::Rc;
 
 use crate::geometry::mesh::Mesh;
-use crate::geometry::{BoundingVolume, Intersectable, Shape, Transformable, TriangleStorage};
+use crate::geometry::motion::{intersect_transformed, Motion};
+use crate::geometry::{
+    BoundingVolume, Intersectable, Shape, Transformable, TriangleStorage, AABB, EMITTER_TIME,
+};
 use crate::intersection::Intersection;
+use crate::light::Emitter;
 use crate::material::Material;
 use crate::math::{Matrix4, Transform};
 use crate::ray::Ray;
@@ -11,6 +15,7 @@ pub struct Instance<V, S> {
     mesh: Rc<Mesh<V, S>>,
     model_matrix: Matrix4,
     inverse_model_matrix: Matrix4,
+    motion: Option<Motion>,
     material: Rc<Material>,
 }
 
@@ -20,9 +25,15 @@ impl<'a, V: BoundingVolume, S: TriangleStorage<'a>> Instance<V, S> {
             mesh,
             model_matrix: Matrix4::identity(),
             inverse_model_matrix: Matrix4::identity(),
+            motion: None,
             material,
         }
     }
+
+    /// Move the instance during the frame, replaces its transforms
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.motion = Some(motion);
+    }
 }
 
 impl<V: BoundingVolume, S: for<'a> TriangleStorage<'a>> Transformable for Instance<V, S> {
@@ -43,23 +54,43 @@ impl<V: BoundingVolume, S: for<'a> TriangleStorage<'a>> Transformable for Instan
 }
 
 impl<V: BoundingVolume, S: for<'a> TriangleStorage<'a>> Intersectable for Instance<V, S> {
-    fn intersect(&self, ray: Ray, cull: bool) -> Option<Intersection> {
-        let inverse_model_matrix = self.inverse_model_matrix;
-        let new_ray = Ray::new(
-            inverse_model_matrix * ray.origin,
-            (inverse_model_matrix * ray.direction).normalize(),
-            Some(ray.medium_refraction),
-        );
-
-        self.mesh.as_ref().intersect(new_ray, cull).map(|mut i| {
-            i.normal = (inverse_model_matrix.transpose() * i.normal).normalize();
-            i.point = self.model_matrix * i.point;
-            i.t = (ray.origin - i.point).length().abs();
+    fn intersect(&self, ray: Ray, cull: bool) -> Option<Intersection<'_>> {
+        let (model_matrix, inverse_model_matrix) = match &self.motion {
+            None => (self.model_matrix, self.inverse_model_matrix),
+            Some(motion) => motion.matrices_at(ray.time),
+        };
+
+        intersect_transformed(ray, model_matrix, inverse_model_matrix, |new_ray| {
+            self.mesh.as_ref().intersect(new_ray, cull)
+        })
+        .map(|mut i| {
             i.shape = self;
 
             i
         })
     }
+
+    fn bounds(&self) -> Option<AABB> {
+        let bounds = self.mesh.bounds()?;
+
+        match &self.motion {
+            None => Some(bounds.transformed(&self.model_matrix)),
+            Some(motion) => Some(motion.bounds(&bounds)),
+        }
+    }
+
+    fn emitters(&self) -> Vec<Emitter> {
+        // The instance's material replaces the mesh's
+        if !self.material.is_emissive() {
+            return vec![];
+        }
+        let matrix = match &self.motion {
+            None => self.model_matrix,
+            Some(motion) => motion.matrix_at(EMITTER_TIME),
+        };
+
+        self.mesh.triangle_emitters(&matrix, Some(&self.material))
+    }
 }
 
 impl<V: BoundingVolume, S: for<'a> TriangleStorage<'a>> Shape for Instance<V, S> {
@@ -1,15 +1,22 @@
 use std::rc::Rc;
 
+use super::motion::{intersect_transformed, Motion};
 use super::triangle::Normal;
-use super::{BoundingVolume, Intersectable, Material, Transformable, Triangle, TriangleStorage};
+use super::{
+    BoundingVolume, Intersectable, Material, Transformable, Triangle, TriangleStorage, AABB,
+    EMITTER_TIME,
+};
 use crate::intersection::Intersection;
-use crate::math::{Point3, Transform};
+use crate::light::Emitter;
+use crate::math::{Matrix4, Point3, Transform};
 use crate::ray::Ray;
 
 #[derive(Debug)]
 pub struct Mesh<V, S> {
     storage: S,
     bounding_volume: V,
+    /// The motion during the frame and the world space bounds covering it
+    motion: Option<(Motion, AABB)>,
 }
 
 impl<'a, V: BoundingVolume, S: TriangleStorage<'a>> Mesh<V, S> {
@@ -20,6 +27,7 @@ impl<'a, V: BoundingVolume, S: TriangleStorage<'a>> Mesh<V, S> {
         Self {
             storage,
             bounding_volume,
+            motion: None,
         }
     }
 
@@ -86,7 +94,7 @@ impl<'a, V: BoundingVolume, S: TriangleStorage<'a>> Mesh<V, S> {
 
     fn from_triangles(vertices: &[Point3], material: Rc<Material>) -> Vec<Triangle> {
         assert!(
-            vertices.len() % 3 == 0,
+            vertices.len().is_multiple_of(3),
             "Number of vertices should be a multiple of 3"
         );
         (0..vertices.len() / 3)
@@ -125,7 +133,68 @@ impl<V: BoundingVolume, S: for<'a> TriangleStorage<'a>> Transformable for Mesh<V
 }
 
 impl<V: BoundingVolume, S: for<'a> TriangleStorage<'a>> Intersectable for Mesh<V, S> {
-    fn intersect(&self, ray: Ray, cull: bool) -> Option<Intersection> {
+    fn intersect(&self, ray: Ray, cull: bool) -> Option<Intersection<'_>> {
+        match &self.motion {
+            None => self.intersect_static(ray, cull),
+            Some((motion, bounds)) => {
+                if !bounds.intersect(ray) {
+                    return None;
+                }
+                let (model_matrix, inverse_model_matrix) = motion.matrices_at(ray.time);
+
+                intersect_transformed(ray, model_matrix, inverse_model_matrix, |new_ray| {
+                    self.intersect_static(new_ray, cull)
+                })
+            }
+        }
+    }
+
+    fn bounds(&self) -> Option<AABB> {
+        match &self.motion {
+            None => Some(AABB::from_triangles(&mut self.storage.all())),
+            Some((_, bounds)) => Some(bounds.clone()),
+        }
+    }
+
+    fn emitters(&self) -> Vec<Emitter> {
+        let matrix = match &self.motion {
+            None => Matrix4::identity(),
+            Some((motion, _)) => motion.matrix_at(EMITTER_TIME),
+        };
+
+        self.triangle_emitters(&matrix, None)
+    }
+}
+
+impl<V: BoundingVolume, S: for<'a> TriangleStorage<'a>> Mesh<V, S> {
+    /// Move the mesh during the frame, the triangles are treated as being in
+    /// object space
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.storage.build();
+        let bounds = motion.bounds(&AABB::from_triangles(&mut self.storage.all()));
+
+        self.motion = Some((motion, bounds));
+    }
+
+    /// The emissive triangles placed by `matrix`, `material` replaces the
+    /// triangles' own materials when given
+    pub(super) fn triangle_emitters(
+        &self,
+        matrix: &Matrix4,
+        material: Option<&Rc<Material>>,
+    ) -> Vec<Emitter> {
+        self.storage
+            .all()
+            .filter_map(|triangle| {
+                triangle.emitter(
+                    matrix,
+                    material.unwrap_or_else(|| triangle.shared_material()),
+                )
+            })
+            .collect()
+    }
+
+    fn intersect_static(&self, ray: Ray, cull: bool) -> Option<Intersection<'_>> {
         if !self.bounding_volume.intersect(ray) {
             return None;
         }
@@ -2,6 +2,7 @@ mod aabb;
 mod extent_volume;
 mod instance;
 mod mesh;
+mod motion;
 mod octtree;
 mod plane;
 mod simple_triangle_storage;
@@ -12,6 +13,7 @@ pub use self::aabb::AABB;
 pub use self::extent_volume::ExtentVolume;
 pub use self::instance::Instance;
 pub use self::mesh::Mesh;
+pub use self::motion::Motion;
 pub use self::plane::Plane;
 pub use self::sphere::Sphere;
 pub use self::triangle::Triangle;
@@ -20,12 +22,29 @@ pub use self::octtree::Octree;
 pub use self::simple_triangle_storage::SimpleTriangleStorage;
 
 use crate::intersection::Intersection;
+use crate::light::Emitter;
 use crate::material::Material;
 use crate::math::Transform;
 use crate::ray::Ray;
 
+/// Moving shapes are sampled as lights where they are at this time. Their
+/// emitters stay there for the whole frame, so light cast by a moving emitter
+/// isn't blurred, only the emitter itself is where rays hit it.
+const EMITTER_TIME: f32 = 0.5;
+
 pub trait Intersectable {
-    fn intersect(&self, ray: Ray, cull: bool) -> Option<Intersection>;
+    fn intersect(&self, ray: Ray, cull: bool) -> Option<Intersection<'_>>;
+
+    /// World space bounds covering the whole frame, `None` for shapes without
+    /// bounds like planes
+    fn bounds(&self) -> Option<AABB>;
+
+    /// The parts of the shape with an emissive material, in world space at
+    /// `EMITTER_TIME`. Shapes without bounds can't be sampled as lights and
+    /// have none.
+    fn emitters(&self) -> Vec<Emitter> {
+        vec![]
+    }
 }
 
 pub trait Transformable {
@@ -0,0 +1,129 @@
+use super::AABB;
+use crate::intersection::Intersection;
+use crate::math::Matrix4;
+use crate::ray::Ray;
+
+/// Movement of a shape during a frame as keyframed object to world matrices.
+/// Between keyframes the matrices are interpolated linearly, which is exact
+/// for translation and scaling. Rotations should be keyframed densely enough
+/// that the shape doesn't visibly shrink.
+#[derive(Debug, Clone)]
+pub struct Motion {
+    /// Sorted by time, 0 is the start of the frame and 1 the start of the
+    /// next one
+    keys: Vec<(f32, Matrix4)>,
+}
+
+impl Motion {
+    pub fn new(mut keys: Vec<(f32, Matrix4)>) -> Self {
+        assert!(!keys.is_empty(), "Motion needs at least one keyframe");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self { keys }
+    }
+
+    /// The object to world matrix at `time` and its inverse
+    pub fn matrices_at(&self, time: f32) -> (Matrix4, Matrix4) {
+        let matrix = self.matrix_at(time);
+        let inverse = matrix
+            .inverse()
+            .expect("Object to world matrices should be invertible");
+
+        (matrix, inverse)
+    }
+
+    /// The object to world matrix at `time`, the first and last keyframes hold
+    /// outside of the keyframed range
+    pub fn matrix_at(&self, time: f32) -> Matrix4 {
+        let next = self.keys.iter().position(|(key_time, _)| *key_time > time);
+        let index = match next {
+            None => return self.keys[self.keys.len() - 1].1,
+            Some(0) => return self.keys[0].1,
+            Some(next) => next - 1,
+        };
+
+        let ((from_time, from), (to_time, to)) = (self.keys[index], self.keys[index + 1]);
+        let t = (time - from_time) / (to_time - from_time);
+        let mut result = from;
+        for i in 0..4 {
+            for j in 0..4 {
+                result[(i, j)] = from[(i, j)] * (1.0 - t) + to[(i, j)] * t;
+            }
+        }
+
+        result
+    }
+
+    /// World space bounds of `bounds` in object space over the whole motion.
+    /// Points move linearly between keyframes so the bounds at the keyframes
+    /// cover everything in between.
+    pub fn bounds(&self, bounds: &AABB) -> AABB {
+        let (_, first) = &self.keys[0];
+
+        self.keys[1..]
+            .iter()
+            .fold(bounds.transformed(first), |result, (_, matrix)| {
+                result.union(&bounds.transformed(matrix))
+            })
+    }
+}
+
+/// Intersect a shape placed in the world by `model_matrix` by moving `ray`
+/// into its object space and the hit back out.
+pub(super) fn intersect_transformed<'a>(
+    ray: Ray,
+    model_matrix: Matrix4,
+    inverse_model_matrix: Matrix4,
+    intersect: impl FnOnce(Ray) -> Option<Intersection<'a>>,
+) -> Option<Intersection<'a>> {
+    let new_ray = Ray::new(
+        inverse_model_matrix * ray.origin,
+        (inverse_model_matrix * ray.direction).normalize(),
+        Some(ray.medium_refraction),
+    )
+    .with_time(ray.time);
+
+    intersect(new_ray).map(|mut i| {
+        i.normal = (inverse_model_matrix.transpose() * i.normal).normalize();
+        i.point = model_matrix * i.point;
+        i.t = (ray.origin - i.point).length().abs();
+
+        i
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Point3;
+
+    #[test]
+    fn test_matrix_at_interpolates_between_keys() {
+        let motion = Motion::new(vec![
+            (1.0, Matrix4::translate(4.0, 0.0, 0.0)),
+            (0.0, Matrix4::identity()),
+        ]);
+
+        let halfway = motion.matrix_at(0.5) * Point3::at_origin();
+        assert_eq_within_bound!(halfway.x, 2.0, 1e-5);
+
+        let after = motion.matrix_at(2.0) * Point3::at_origin();
+        assert_eq_within_bound!(after.x, 4.0, 1e-5);
+    }
+
+    #[test]
+    fn test_bounds_cover_motion() {
+        let motion = Motion::new(vec![
+            (0.0, Matrix4::identity()),
+            (1.0, Matrix4::translate(0.0, 3.0, 0.0)),
+        ]);
+        let bounds = motion.bounds(&AABB::new(
+            Point3::new(-1.0, -1.0, -1.0),
+            Point3::new(1.0, 1.0, 1.0),
+        ));
+
+        assert_eq_within_bound!(bounds.min().y, -1.0, 1e-5);
+        assert_eq_within_bound!(bounds.max().y, 4.0, 1e-5);
+        assert_eq_within_bound!(bounds.max().x, 1.0, 1e-5);
+    }
+}
@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use super::{Intersectable, Shape, Transformable};
+use super::{Intersectable, Motion, Shape, Transformable, AABB};
 use crate::intersection::Intersection;
 use crate::material::Material;
 use crate::math::EPSILON;
@@ -10,6 +10,7 @@ use crate::ray::Ray;
 pub struct Plane {
     pub origin: Point3,
     pub normal: Vector3,
+    motion: Option<Motion>,
     material: Rc<Material>,
 }
 
@@ -18,9 +19,29 @@ impl Plane {
         Plane {
             origin,
             normal: normal.normalize(),
+            motion: None,
             material,
         }
     }
+
+    /// Move the plane during the frame, replaces its transforms
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.motion = Some(motion);
+    }
+
+    fn origin_and_normal_at(&self, time: f32) -> (Point3, Vector3) {
+        match &self.motion {
+            None => (self.origin, self.normal),
+            Some(motion) => {
+                let (matrix, inverse) = motion.matrices_at(time);
+
+                (
+                    matrix * self.origin,
+                    (inverse.transpose() * self.normal).normalize(),
+                )
+            }
+        }
+    }
 }
 
 impl Shape for Plane {
@@ -30,26 +51,31 @@ impl Shape for Plane {
 }
 
 impl Intersectable for Plane {
-    fn intersect(&self, ray: Ray, _: bool) -> Option<Intersection> {
-        let denominator = self.normal.dot(&ray.direction);
+    fn intersect(&self, ray: Ray, _: bool) -> Option<Intersection<'_>> {
+        let (origin, normal) = self.origin_and_normal_at(ray.time);
+        let denominator = normal.dot(&ray.direction);
 
         if denominator.abs() <= EPSILON {
             return None;
         }
 
-        let t = ((self.origin - ray.origin).dot(&self.normal)) / denominator;
+        let t = ((origin - ray.origin).dot(&normal)) / denominator;
 
         if t >= 0.0 {
             let intersection_point = (ray.origin + ray.direction * t).as_point();
 
             let intersection =
-                Intersection::new(t, self, intersection_point, ray, self.normal, false, None);
+                Intersection::new(t, self, intersection_point, ray, normal, false, None);
 
             return Some(intersection);
         }
 
         None
     }
+
+    fn bounds(&self) -> Option<AABB> {
+        None
+    }
 }
 
 impl Transformable for Plane {
@@ -2,17 +2,19 @@ use std::f32::consts::PI;
 use std::rc::Rc;
 
 use crate::intersection::Intersection;
+use crate::light::Emitter;
 use crate::material::Material;
-use crate::math::{Point3, Transform};
+use crate::math::{Point3, Transform, Vector3};
 use crate::ray::Ray;
 use crate::texture::TextureCoord;
 
-use super::{Intersectable, Shape, Transformable};
+use super::{Intersectable, Motion, Shape, Transformable, AABB, EMITTER_TIME};
 
 #[derive(Debug)]
 pub struct Sphere {
     pub origin: Point3,
     pub radius: f32,
+    motion: Option<Motion>,
     material: Rc<Material>,
 }
 
@@ -21,9 +23,22 @@ impl Sphere {
         Self {
             origin,
             radius,
+            motion: None,
             material,
         }
     }
+
+    /// Move the sphere's origin during the frame, replaces its transforms
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.motion = Some(motion);
+    }
+
+    fn origin_at(&self, time: f32) -> Point3 {
+        match &self.motion {
+            None => self.origin,
+            Some(motion) => motion.matrix_at(time) * self.origin,
+        }
+    }
 }
 
 impl Shape for Sphere {
@@ -33,8 +48,9 @@ impl Shape for Sphere {
 }
 
 impl Intersectable for Sphere {
-    fn intersect(&self, ray: Ray, _: bool) -> Option<Intersection> {
-        let v = ray.origin - self.origin;
+    fn intersect(&self, ray: Ray, _: bool) -> Option<Intersection<'_>> {
+        let origin = self.origin_at(ray.time);
+        let v = ray.origin - origin;
         let a = ray.direction.dot(&v);
         let b = -a;
         let c = a.powf(2.0) - v.length().powf(2.0) + self.radius.powf(2.0);
@@ -59,7 +75,7 @@ impl Intersectable for Sphere {
         if hit {
             assert!(t.is_some());
             let point: Point3 = (ray.origin + ray.direction * t.unwrap()).as_point();
-            let normal = (point - self.origin).normalize();
+            let normal = (point - origin).normalize();
             let texture_coord = TextureCoord::new(
                 normal.x.atan2(normal.z) / (2.0 * PI) + 0.5,
                 normal.y * 0.5 + 0.5,
@@ -80,6 +96,32 @@ impl Intersectable for Sphere {
 
         None
     }
+
+    fn bounds(&self) -> Option<AABB> {
+        let origin = AABB::new(self.origin, self.origin);
+        let origins = match &self.motion {
+            None => origin,
+            Some(motion) => motion.bounds(&origin),
+        };
+        let radius = Vector3::new(self.radius, self.radius, self.radius);
+
+        Some(AABB::new(
+            (origins.min() + radius * -1.0).as_point(),
+            (origins.max() + radius).as_point(),
+        ))
+    }
+
+    fn emitters(&self) -> Vec<Emitter> {
+        if !self.material.is_emissive() {
+            return vec![];
+        }
+
+        vec![Emitter::Sphere {
+            center: self.origin_at(EMITTER_TIME),
+            radius: self.radius,
+            material: Rc::clone(&self.material),
+        }]
+    }
 }
 
 impl Transformable for Sphere {
@@ -100,7 +142,7 @@ mod tests {
     use crate::ray::Ray;
 
     fn build_test_material() -> Rc<Material> {
-        let color = Color::new(0, 0, 0);
+        let color = Color::black();
 
         Rc::new(
             MaterialTemplate::new(
@@ -1,13 +1,14 @@
 use std::rc::Rc;
 
 use crate::intersection::Intersection;
+use crate::light::Emitter;
 use crate::material::Material;
 use crate::math::EPSILON;
-use crate::math::{Point3, Transform, Vector3};
+use crate::math::{Matrix4, Point3, Transform, Vector3};
 use crate::ray::Ray;
 use crate::texture::TextureCoord;
 
-use super::{Intersectable, Shape, Transformable};
+use super::{Intersectable, Shape, Transformable, AABB};
 
 #[cfg(feature = "stats")]
 pub mod stats {
@@ -91,7 +92,7 @@ impl Shape for Triangle {
 }
 
 impl Intersectable for Triangle {
-    fn intersect(&self, ray: Ray, cull: bool) -> Option<Intersection> {
+    fn intersect(&self, ray: Ray, cull: bool) -> Option<Intersection<'_>> {
         #[cfg(feature = "stats")]
         record_triangle_intersection();
 
@@ -155,9 +156,37 @@ impl Intersectable for Triangle {
 
         None
     }
+
+    fn bounds(&self) -> Option<AABB> {
+        Some(AABB::from_triangle(self))
+    }
+
+    fn emitters(&self) -> Vec<Emitter> {
+        self.emitter(&Matrix4::identity(), &self.material)
+            .into_iter()
+            .collect()
+    }
 }
 
 impl Triangle {
+    /// The triangle placed by `matrix` as an emitter when `material` is
+    /// emissive
+    pub(super) fn emitter(&self, matrix: &Matrix4, material: &Rc<Material>) -> Option<Emitter> {
+        if !material.is_emissive() {
+            return None;
+        }
+
+        Some(Emitter::Triangle {
+            vertices: self.vertices.map(|vertex| *matrix * vertex),
+            texture_coords: self.texture_coords,
+            material: Rc::clone(material),
+        })
+    }
+
+    pub(super) fn shared_material(&self) -> &Rc<Material> {
+        &self.material
+    }
+
     fn normal_at_intersection(&self, u: f32, v: f32) -> Vector3 {
         match self.normal {
             Normal::Face(normal) => normal,
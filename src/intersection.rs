@@ -23,7 +23,7 @@ impl<'a> Intersection<'a> {
         normal: Vector3,
         inside: bool,
         texture_coord: Option<TextureCoord>,
-    ) -> Intersection {
+    ) -> Intersection<'a> {
         Intersection {
             t,
             shape,
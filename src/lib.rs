@@ -5,20 +5,32 @@ mod intersection;
 mod light;
 mod ray;
 
+pub mod animation;
+pub mod aov;
 pub mod camera;
+pub mod checkpoint;
 pub mod color;
 mod config;
+pub mod denoiser;
+pub mod filter;
+pub mod framebuffer;
 pub mod geometry;
 pub mod material;
 pub mod mesh_loader;
 pub mod renderer;
+pub mod sampler;
 pub mod scene;
+pub mod scheduler;
 pub mod texture;
+pub mod tone_mapping;
 
 pub use self::camera::Camera;
 pub use self::color::Color;
 pub use self::config::Config;
 pub use self::config_loader::ConfigLoader;
+pub use self::filter::Filter;
+pub use self::framebuffer::Framebuffer;
 pub use self::material::{IllumninationModel, Material, MaterialTemplate};
-pub use self::renderer::{Renderer, SuperSampling};
+pub use self::renderer::{Integrator, RenderSettings, Renderer, SuperSampling};
 pub use self::scene::Scene;
+pub use self::tone_mapping::ToneMapping;
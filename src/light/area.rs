@@ -0,0 +1,252 @@
+use std::f32::consts::PI;
+
+use serde::Deserialize;
+
+use crate::color::Color;
+use crate::math::{Point2, Point3, Vector3};
+
+use super::{Light, LightSample};
+
+/// The shape of an area light, rectangles and disks emit from their front
+/// only while spheres emit in all directions.
+#[derive(Deserialize, Debug, Copy, Clone)]
+#[serde(tag = "type")]
+pub enum AreaShape {
+    Rectangle { width: f32, height: f32 },
+    Disk { radius: f32 },
+    Sphere { radius: f32 },
+}
+
+/// A light with a surface, points that only see part of it are in the
+/// penumbra of a soft shadow. Area lights aren't visible to camera rays.
+pub struct Area {
+    origin: Point3,
+    /// The direction rectangles and disks face
+    normal: Vector3,
+    /// Along the width of rectangles
+    tangent: Vector3,
+    /// Along the height of rectangles
+    bitangent: Vector3,
+    shape: AreaShape,
+    pub color: Color,
+    intensity: f32,
+    samples: u32,
+    diffuse: bool,
+    specular: bool,
+}
+
+impl Area {
+    /// An area light at `origin` facing `direction`, the height of rectangles
+    /// runs along `up` projected onto their plane. From far away it is as
+    /// bright as a point light of the same `intensity`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        origin: Point3,
+        direction: Vector3,
+        up: Vector3,
+        shape: AreaShape,
+        color: Color,
+        intensity: f32,
+        samples: u32,
+        diffuse: bool,
+        specular: bool,
+    ) -> Self {
+        let normal = direction.normalize();
+        let tangent = up.cross(&normal);
+        let (tangent, bitangent) = if tangent.length() > 1e-6 {
+            let tangent = tangent.normalize();
+
+            (tangent, normal.cross(&tangent))
+        } else {
+            normal.orthonormal_basis()
+        };
+
+        Self {
+            origin,
+            normal,
+            tangent,
+            bitangent,
+            shape,
+            color,
+            intensity,
+            samples: samples.max(1),
+            diffuse,
+            specular,
+        }
+    }
+
+    /// The area the light appears to have when seen head on, its radiance
+    /// is spread across it
+    fn projected_area(&self) -> f32 {
+        match self.shape {
+            AreaShape::Rectangle { width, height } => width * height,
+            AreaShape::Disk { radius } | AreaShape::Sphere { radius } => PI * radius * radius,
+        }
+    }
+
+    fn radiance(&self) -> Color {
+        self.color * (self.intensity / self.projected_area())
+    }
+
+    /// Sample a point uniformly on the area of a flat light, the pdf is
+    /// converted to solid angle as seen from `point`
+    fn sample_flat(&self, point: Point3, on_light: Point3) -> Option<LightSample> {
+        let to_light = on_light - point;
+        let distance = to_light.length();
+        let direction = to_light.normalize();
+        let cos_light = -direction.dot(&self.normal);
+        if cos_light <= 0.0 || distance <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            point: on_light,
+            direction,
+            distance,
+            pdf: distance * distance / (self.projected_area() * cos_light),
+            radiance: self.radiance(),
+        })
+    }
+}
+
+impl Light for Area {
+    fn sample(&self, point: Point3, sample: Point2) -> Option<LightSample> {
+        match self.shape {
+            AreaShape::Rectangle { width, height } => {
+                let on_light = self.origin
+                    + self.tangent * ((sample.x - 0.5) * width)
+                    + self.bitangent * ((sample.y - 0.5) * height);
+
+                self.sample_flat(point, on_light.as_point())
+            }
+            AreaShape::Disk { radius } => {
+                let r = radius * sample.x.sqrt();
+                let phi = 2.0 * PI * sample.y;
+                let on_light =
+                    self.origin + self.tangent * (r * phi.cos()) + self.bitangent * (r * phi.sin());
+
+                self.sample_flat(point, on_light.as_point())
+            }
+            AreaShape::Sphere { radius } => {
+                sample_sphere(self.origin, radius, point, sample, self.radiance())
+            }
+        }
+    }
+
+    fn shadow_samples(&self) -> u32 {
+        self.samples
+    }
+
+    fn diffuse(&self) -> bool {
+        self.diffuse
+    }
+
+    fn specular(&self) -> bool {
+        self.specular
+    }
+}
+
+/// Sample the cone of directions in which the sphere around `center` is
+/// visible from `point` uniformly. `None` when `point` is inside of it.
+pub(super) fn sample_sphere(
+    center: Point3,
+    radius: f32,
+    point: Point3,
+    sample: Point2,
+    radiance: Color,
+) -> Option<LightSample> {
+    let to_center = center - point;
+    let center_distance = to_center.length();
+    if center_distance <= radius {
+        return None;
+    }
+
+    let axis = to_center.normalize();
+    let sin_squared_max = radius * radius / (center_distance * center_distance);
+    let cos_max = (1.0 - sin_squared_max).max(0.0).sqrt();
+    // 1 - cos_max without cancellation for small and distant spheres
+    let one_minus_cos_max = sin_squared_max / (1.0 + cos_max);
+
+    let cos_theta = 1.0 - sample.x * one_minus_cos_max;
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * sample.y;
+    let (tangent, bitangent) = axis.orthonormal_basis();
+    let direction = (tangent * (sin_theta * phi.cos())
+        + bitangent * (sin_theta * phi.sin())
+        + axis * cos_theta)
+        .normalize();
+
+    // The nearer intersection of the ray with the sphere
+    let distance = center_distance * cos_theta
+        - (radius * radius - center_distance * center_distance * sin_theta * sin_theta)
+            .max(0.0)
+            .sqrt();
+
+    Some(LightSample {
+        point: (point + direction * distance).as_point(),
+        direction,
+        distance,
+        pdf: 1.0 / (2.0 * PI * one_minus_cos_max),
+        radiance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::tests::reflected;
+    use crate::math::Rng;
+
+    fn light(shape: AreaShape) -> Area {
+        Area::new(
+            Point3::new(0.0, 10.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            shape,
+            Color::white(),
+            100.0,
+            1,
+            true,
+            true,
+        )
+    }
+
+    #[test]
+    fn test_small_lights_match_point_light() {
+        for shape in [
+            AreaShape::Rectangle {
+                width: 0.2,
+                height: 0.1,
+            },
+            AreaShape::Disk { radius: 0.1 },
+            AreaShape::Sphere { radius: 0.1 },
+        ] {
+            assert_eq_within_bound!(reflected(&light(shape), Point3::at_origin(), 7), 1.0, 0.01);
+        }
+    }
+
+    #[test]
+    fn test_flat_lights_only_emit_forwards() {
+        let light = light(AreaShape::Disk { radius: 1.0 });
+        let above = Point3::new(0.0, 11.0, 0.0);
+
+        assert!(light.sample(above, Point2::new(0.5, 0.5)).is_none());
+    }
+
+    #[test]
+    fn test_sphere_samples_lie_on_surface() {
+        let light = light(AreaShape::Sphere { radius: 2.0 });
+        let mut rng = Rng::new(3);
+
+        for _ in 0..100 {
+            let sample = light
+                .sample(
+                    Point3::at_origin(),
+                    Point2::new(rng.next_f32(), rng.next_f32()),
+                )
+                .unwrap();
+
+            assert_eq_within_bound!((sample.point - light.origin).length(), 2.0, 1e-3);
+        }
+    }
+}
@@ -1,12 +1,13 @@
 use std::f32;
 
 use crate::color::Color;
-use crate::intersection::Intersection;
-use crate::material::Material;
-use crate::math::Vector3;
-use crate::ray::Ray;
+use crate::math::{Point2, Point3, Vector3};
 
-use super::Light;
+use super::{Light, LightSample};
+
+/// Distance along the direction of the light at which sample points are
+/// placed
+const FAR_AWAY: f32 = 1e6;
 
 pub struct Directional {
     pub direction: Vector3,
@@ -42,70 +43,21 @@ impl Directional {
 }
 
 impl Light for Directional {
-    fn create_shadow_ray(
-        &self,
-        intersection: &Intersection,
-        medium_refraction: Option<f32>,
-    ) -> Ray {
-        let direction = self.inverse_direction;
-        Ray::new(
-            (intersection.point + direction * 1e-3).as_point(),
-            direction,
-            medium_refraction,
-        )
+    fn sample(&self, point: Point3, _sample: Point2) -> Option<LightSample> {
+        Some(LightSample {
+            point: (point + -self.direction * FAR_AWAY).as_point(),
+            direction: self.inverse_direction,
+            distance: f32::INFINITY,
+            pdf: 1.0,
+            radiance: self.color * self.intensity(f32::INFINITY),
+        })
     }
 
-    fn distance_to_light(&self, _intersection: &Intersection) -> f32 {
-        f32::INFINITY
+    fn diffuse(&self) -> bool {
+        self.diffuse
     }
 
-    fn diffuse_color(
-        &self,
-        intersection: &Intersection,
-        material: &Material,
-        distance_to_light: f32,
-    ) -> Option<Color> {
-        if !self.diffuse {
-            return None;
-        }
-
-        let dot = self.inverse_direction.dot(&intersection.normal);
-
-        if dot > 0.0 {
-            Some(
-                (self.color * material.diffuse_color(intersection.texture_coord))
-                    * dot
-                    * self.intensity(distance_to_light),
-            )
-        } else {
-            None
-        }
-    }
-
-    fn specular_color(
-        &self,
-        intersection: &Intersection,
-        material: &Material,
-        ray: &Ray,
-        distance_to_light: f32,
-    ) -> Option<Color> {
-        if !self.specular {
-            return None;
-        }
-
-        let dot = ray
-            .direction
-            .dot(&self.inverse_direction.reflect(&intersection.normal));
-
-        if dot > 0.0 {
-            let spec = dot.powf(material.specular_exponent);
-            Some(
-                (self.color * material.specular_color(intersection.texture_coord))
-                    * spec
-                    * self.intensity(distance_to_light),
-            )
-        } else {
-            None
-        }
+    fn specular(&self) -> bool {
+        self.specular
     }
 }
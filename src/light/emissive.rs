@@ -0,0 +1,250 @@
+use std::f32::consts::PI;
+use std::rc::Rc;
+
+use crate::color::Color;
+use crate::material::Material;
+use crate::math::{Distribution1D, Point2, Point3};
+use crate::texture::TextureCoord;
+
+use super::area::sample_sphere;
+use super::{Light, LightSample};
+
+/// A part of a shape with an emissive material, in world space
+#[derive(Debug, Clone)]
+pub enum Emitter {
+    Sphere {
+        center: Point3,
+        radius: f32,
+        material: Rc<Material>,
+    },
+    Triangle {
+        vertices: [Point3; 3],
+        texture_coords: Option<[TextureCoord; 3]>,
+        material: Rc<Material>,
+    },
+}
+
+impl Emitter {
+    fn area(&self) -> f32 {
+        match self {
+            Emitter::Sphere { radius, .. } => 4.0 * PI * radius * radius,
+            Emitter::Triangle { vertices, .. } => {
+                (vertices[1] - vertices[0])
+                    .cross(&(vertices[2] - vertices[0]))
+                    .length()
+                    * 0.5
+            }
+        }
+    }
+
+    fn material(&self) -> &Material {
+        match self {
+            Emitter::Sphere { material, .. } | Emitter::Triangle { material, .. } => material,
+        }
+    }
+
+    /// Roughly proportional to the light the emitter gives off, textures are
+    /// ignored
+    fn power(&self) -> f32 {
+        let material = self.material();
+
+        self.area() * material.emission_color.luminance() * material.emission_strength
+    }
+
+    fn sample(&self, point: Point3, sample: Point2) -> Option<LightSample> {
+        match self {
+            Emitter::Sphere {
+                center,
+                radius,
+                material,
+            } => {
+                let mut light_sample =
+                    sample_sphere(*center, *radius, point, sample, Color::black())?;
+                // The same mapping as sphere intersections
+                let normal = (light_sample.point - *center).normalize();
+                let uv = TextureCoord::new(
+                    normal.x.atan2(normal.z) / (2.0 * PI) + 0.5,
+                    normal.y * 0.5 + 0.5,
+                );
+                light_sample.radiance = material.emission(Some(uv));
+
+                Some(light_sample)
+            }
+            Emitter::Triangle {
+                vertices,
+                texture_coords,
+                material,
+            } => {
+                // Uniform barycentric coordinates
+                let root = sample.x.sqrt();
+                let u = root * (1.0 - sample.y);
+                let v = root * sample.y;
+                let w = 1.0 - u - v;
+                let ab = vertices[1] - vertices[0];
+                let ac = vertices[2] - vertices[0];
+                let on_light = (vertices[0] + ab * u + ac * v).as_point();
+
+                let to_light = on_light - point;
+                let distance = to_light.length();
+                let direction = to_light.normalize();
+                let normal = ab.cross(&ac);
+                // Triangles emit from both sides
+                let cos_light = direction.dot(&normal.normalize()).abs();
+                if cos_light <= 1e-6 || distance <= 0.0 {
+                    return None;
+                }
+                let uv = texture_coords.map(|[ta, tb, tc]| {
+                    TextureCoord::new(
+                        w * ta.x + u * tb.x + v * tc.x,
+                        w * ta.y + u * tb.y + v * tc.y,
+                    )
+                });
+
+                Some(LightSample {
+                    point: on_light,
+                    direction,
+                    distance,
+                    pdf: distance * distance / (normal.length() * 0.5 * cos_light),
+                    radiance: material.emission(uv),
+                })
+            }
+        }
+    }
+}
+
+/// The emissive surfaces of an object sampled as one light, each emitter is
+/// picked in proportion to its power.
+pub struct Emissive {
+    emitters: Vec<Emitter>,
+    distribution: Distribution1D,
+    samples: u32,
+}
+
+impl Emissive {
+    /// `emitters` must not be empty
+    pub fn new(emitters: Vec<Emitter>, samples: u32) -> Self {
+        debug_assert!(!emitters.is_empty());
+
+        Self {
+            distribution: Distribution1D::new(emitters.iter().map(Emitter::power).collect()),
+            emitters,
+            samples: samples.max(1),
+        }
+    }
+}
+
+impl Light for Emissive {
+    fn sample(&self, point: Point3, sample: Point2) -> Option<LightSample> {
+        let count = self.emitters.len() as f32;
+        let (position, pdf, index) = self.distribution.sample(sample.x);
+        // Reuse the position within the emitter's bucket as a fresh sample
+        let remapped = Point2::new((position * count - index as f32).clamp(0.0, 1.0), sample.y);
+        let mut light_sample = self.emitters[index].sample(point, remapped)?;
+        light_sample.pdf *= pdf / count;
+
+        Some(light_sample)
+    }
+
+    fn shadow_samples(&self) -> u32 {
+        self.samples
+    }
+
+    fn diffuse(&self) -> bool {
+        true
+    }
+
+    fn specular(&self) -> bool {
+        true
+    }
+
+    fn emits_radiance(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::tests::reflected;
+    use crate::material::{IllumninationModel, MaterialTemplate};
+    use crate::math::Rng;
+
+    fn emissive_material(strength: f32) -> Rc<Material> {
+        let template = MaterialTemplate::new(
+            Color::black(),
+            Color::black(),
+            Color::black(),
+            0.0,
+            IllumninationModel::Diffuse,
+            None,
+            None,
+        );
+
+        Rc::new(template.build_material(|material| {
+            material.emission_color = Color::white();
+            material.emission_strength = strength;
+        }))
+    }
+
+    #[test]
+    fn test_triangles_match_form_factor() {
+        // A 4 by 4 square 1 above the surface covers 83% of its view
+        let material = emissive_material(2.0);
+        let size = 2.0;
+        let corners = [
+            Point3::new(-size, 1.0, -size),
+            Point3::new(size, 1.0, -size),
+            Point3::new(size, 1.0, size),
+            Point3::new(-size, 1.0, size),
+        ];
+        let light = Emissive::new(
+            vec![
+                Emitter::Triangle {
+                    vertices: [corners[0], corners[1], corners[2]],
+                    texture_coords: None,
+                    material: Rc::clone(&material),
+                },
+                Emitter::Triangle {
+                    vertices: [corners[0], corners[2], corners[3]],
+                    texture_coords: None,
+                    material,
+                },
+            ],
+            1,
+        );
+
+        assert_eq_within_bound!(reflected(&light, Point3::at_origin(), 11), 1.662, 0.03);
+    }
+
+    #[test]
+    fn test_emitters_are_picked_by_power() {
+        let light = Emissive::new(
+            vec![
+                Emitter::Sphere {
+                    center: Point3::new(-5.0, 5.0, 0.0),
+                    radius: 1.0,
+                    material: emissive_material(1.0),
+                },
+                Emitter::Sphere {
+                    center: Point3::new(5.0, 5.0, 0.0),
+                    radius: 1.0,
+                    material: emissive_material(9.0),
+                },
+            ],
+            1,
+        );
+        let mut rng = Rng::new(2);
+
+        let brighter = (0..1000)
+            .filter_map(|_| {
+                light.sample(
+                    Point3::at_origin(),
+                    Point2::new(rng.next_f32(), rng.next_f32()),
+                )
+            })
+            .filter(|sample| sample.point.x > 0.0)
+            .count();
+
+        assert!((850..=950).contains(&brighter), "{}", brighter);
+    }
+}
@@ -0,0 +1,252 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+use std::path::Path;
+
+use image::DynamicImage;
+
+use crate::color::Color;
+use crate::math::{Distribution2D, Point2, Point3, Vector3};
+use crate::tone_mapping::srgb_to_linear;
+
+use super::{Light, LightSample};
+
+/// Distance along the sampled direction at which sample points are placed
+const FAR_AWAY: f32 = 1e6;
+
+/// Light arriving from infinitely far away in every direction, looked up in
+/// an equirectangular image. The center of the image is in the direction of
+/// the negative z axis and its top is straight up, like the equirectangular
+/// camera.
+pub struct Environment {
+    pixels: Vec<Color>,
+    width: usize,
+    height: usize,
+    /// Counter clockwise around the y axis, in radians
+    rotation: f32,
+    intensity: f32,
+    /// Proportional to the luminance of each pixel times the solid angle it
+    /// covers
+    distribution: Distribution2D,
+    samples: u32,
+    background: bool,
+}
+
+impl Environment {
+    /// `pixels` are linear radiance in row major order, the first row is the
+    /// top of the image
+    pub fn new(
+        pixels: Vec<Color>,
+        width: usize,
+        height: usize,
+        rotation: f32,
+        intensity: f32,
+        samples: u32,
+        background: bool,
+    ) -> Self {
+        debug_assert_eq!(pixels.len(), width * height);
+
+        let weights: Vec<f32> = pixels
+            .iter()
+            .enumerate()
+            .map(|(index, pixel)| {
+                let theta = ((index / width) as f32 + 0.5) / height as f32 * PI;
+
+                pixel.luminance().max(0.0) * theta.sin()
+            })
+            .collect();
+
+        Self {
+            distribution: Distribution2D::new(&weights, width, height),
+            pixels,
+            width,
+            height,
+            rotation,
+            intensity,
+            samples: samples.max(1),
+            background,
+        }
+    }
+
+    /// Load the image at `path`, images that aren't stored as floating point
+    /// are assumed to be sRGB encoded
+    pub fn load(
+        path: &Path,
+        rotation: f32,
+        intensity: f32,
+        samples: u32,
+        background: bool,
+    ) -> Result<Self, image::ImageError> {
+        let image = image::open(path)?;
+        let linear = matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        );
+        let image = image.into_rgb32f();
+        let pixels = image
+            .pixels()
+            .map(|pixel| {
+                let color = Color::from(pixel.0);
+
+                match linear {
+                    true => color,
+                    false => color.map(srgb_to_linear),
+                }
+            })
+            .collect();
+
+        Ok(Self::new(
+            pixels,
+            image.width() as usize,
+            image.height() as usize,
+            rotation,
+            intensity,
+            samples,
+            background,
+        ))
+    }
+
+    /// Whether camera rays that miss everything see the environment
+    pub fn is_background(&self) -> bool {
+        self.background
+    }
+
+    /// Radiance arriving from `direction`
+    pub fn radiance(&self, direction: Vector3) -> Color {
+        let uv = self.direction_to_uv(direction);
+        let x = ((uv.x * self.width as f32) as usize).min(self.width - 1);
+        let y = ((uv.y * self.height as f32) as usize).min(self.height - 1);
+
+        self.pixels[y * self.width + x] * self.intensity
+    }
+
+    fn direction_to_uv(&self, direction: Vector3) -> Point2 {
+        let local = rotate_y(direction.normalize(), -self.rotation);
+        let longitude = local.x.atan2(-local.z);
+        let latitude = local.y.clamp(-1.0, 1.0).asin();
+
+        Point2::new(
+            (longitude / (2.0 * PI) + 0.5).rem_euclid(1.0),
+            0.5 - latitude / PI,
+        )
+    }
+
+    fn uv_to_direction(&self, uv: Point2) -> Vector3 {
+        rotate_y(equirectangular_direction(uv), self.rotation)
+    }
+}
+
+impl Light for Environment {
+    fn sample(&self, point: Point3, sample: Point2) -> Option<LightSample> {
+        let (uv, pdf) = self.distribution.sample(sample);
+        let sin_theta = (uv.y * PI).sin();
+        if pdf <= 0.0 || sin_theta <= 0.0 {
+            return None;
+        }
+        let direction = self.uv_to_direction(uv);
+
+        // The image covers 2π by π radians, each pixel shrinks towards the
+        // poles
+        Some(LightSample {
+            point: (point + direction * FAR_AWAY).as_point(),
+            direction,
+            distance: f32::INFINITY,
+            pdf: pdf / (2.0 * PI * PI * sin_theta),
+            radiance: self.radiance(direction),
+        })
+    }
+
+    fn shadow_samples(&self) -> u32 {
+        self.samples
+    }
+
+    fn diffuse(&self) -> bool {
+        true
+    }
+
+    fn specular(&self) -> bool {
+        true
+    }
+
+    fn emits_radiance(&self) -> bool {
+        true
+    }
+}
+
+/// The direction through `uv` in an unrotated equirectangular image, the
+/// center of the image looks down the negative z axis
+pub(super) fn equirectangular_direction(uv: Point2) -> Vector3 {
+    let longitude = (uv.x - 0.5) * 2.0 * PI;
+    let latitude = FRAC_PI_2 - uv.y * PI;
+
+    Vector3::new(
+        longitude.sin() * latitude.cos(),
+        latitude.sin(),
+        -longitude.cos() * latitude.cos(),
+    )
+}
+
+/// Rotate `vector` counter clockwise around the y axis by `angle` radians
+fn rotate_y(vector: Vector3, angle: f32) -> Vector3 {
+    let (sin, cos) = angle.sin_cos();
+
+    Vector3::new(
+        vector.x * cos + vector.z * sin,
+        vector.y,
+        -vector.x * sin + vector.z * cos,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::tests::reflected;
+    use crate::math::Rng;
+
+    fn environment(pixels: Vec<Color>, rotation: f32) -> Environment {
+        Environment::new(pixels, 8, 4, rotation, 1.0, 1, true)
+    }
+
+    #[test]
+    fn test_uniform_environment_irradiance() {
+        let environment = environment(vec![Color::white(); 32], 0.3);
+
+        // A white surface facing up is as bright as a uniform sky
+        assert_eq_within_bound!(reflected(&environment, Point3::at_origin(), 5), 1.0, 0.02);
+    }
+
+    #[test]
+    fn test_samples_follow_bright_pixels() {
+        let mut pixels = vec![Color::white() * 0.01; 32];
+        pixels[8 + 4] = Color::white() * 1000.0;
+        let environment = environment(pixels, 1.0);
+        let bright = environment.uv_to_direction(Point2::new(4.5 / 8.0, 1.5 / 4.0));
+        let mut rng = Rng::new(9);
+
+        let hits = (0..1000)
+            .filter_map(|_| {
+                environment.sample(
+                    Point3::at_origin(),
+                    Point2::new(rng.next_f32(), rng.next_f32()),
+                )
+            })
+            .filter(|sample| environment.radiance(sample.direction).r() > 1.0)
+            .count();
+
+        assert!(hits > 950, "{} samples hit the bright pixel", hits);
+        assert_eq!(environment.radiance(bright), Color::white() * 1000.0);
+    }
+
+    #[test]
+    fn test_direction_round_trip() {
+        let environment = environment(vec![Color::white(); 32], 2.0);
+
+        for direction in [
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.3, 0.5, 0.2).normalize(),
+            Vector3::new(-0.7, -0.1, 0.6).normalize(),
+        ] {
+            let round_trip = environment.uv_to_direction(environment.direction_to_uv(direction));
+
+            assert_eq_vector3!(round_trip, direction, 1e-4);
+        }
+    }
+}
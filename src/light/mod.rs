@@ -1,15 +1,23 @@
 use serde::Deserialize;
 
 use crate::color::Color;
-use crate::intersection::Intersection;
-use crate::material::Material;
-use crate::ray::Ray;
+use crate::math::{Point2, Point3, Vector3};
 
+pub mod area;
 pub mod directional;
+pub mod emissive;
+pub mod environment;
 pub mod point;
+pub mod sky;
+pub mod spot;
 
+pub use self::area::{Area, AreaShape};
 pub use self::directional::Directional;
+pub use self::emissive::{Emissive, Emitter};
+pub use self::environment::Environment;
 pub use self::point::Point;
+pub use self::sky::{Sky, SunPosition};
+pub use self::spot::{Cone, Spot};
 
 #[derive(Deserialize, Debug, Copy, Clone)]
 pub enum Falloff {
@@ -19,21 +27,81 @@ pub enum Falloff {
     InverseLinear,
 }
 
+impl Falloff {
+    /// The intensity of a light at `distance`
+    pub fn attenuate(&self, intensity: f32, distance: f32) -> f32 {
+        match self {
+            Falloff::InverseSquare => 1.0 / (distance * distance) * intensity,
+            Falloff::InverseLinear => 1.0 / distance * intensity,
+        }
+    }
+}
+
+/// Light arriving at a shaded point from a point sampled on a light.
+#[derive(Debug, Copy, Clone)]
+pub struct LightSample {
+    /// The sampled point on the light, far along `direction` for lights at
+    /// infinity
+    pub point: Point3,
+    /// Unit vector from the shaded point towards `point`
+    pub direction: Vector3,
+    /// Distance to `point`, infinite for lights at infinity
+    pub distance: f32,
+    /// Probability density of having sampled `direction`, with respect to
+    /// solid angle. Lights that only reach a point from a single direction,
+    /// like point lights, have a pdf of 1.
+    pub pdf: f32,
+    /// Radiance arriving along `direction` if nothing is in the way. Shading
+    /// leaves out the 1 / π of diffuse reflection, lights that don't
+    /// `emits_radiance` include it and direct lighting applies it to the
+    /// others.
+    pub radiance: Color,
+}
+
 pub trait Light {
-    fn create_shadow_ray(&self, intersection: &Intersection, medium_refraction: Option<f32>)
-        -> Ray;
-    fn distance_to_light(&self, intersection: &Intersection) -> f32;
-    fn diffuse_color(
-        &self,
-        intersection: &Intersection,
-        material: &Material,
-        distance_to_light: f32,
-    ) -> Option<Color>;
-    fn specular_color(
-        &self,
-        intersection: &Intersection,
-        material: &Material,
-        ray: &Ray,
-        distance_to_light: f32,
-    ) -> Option<Color>;
+    /// Sample the light as seen from `point`, `sample` is uniform in [0, 1)².
+    /// `None` when the sampled part of the light doesn't shine on `point`.
+    fn sample(&self, point: Point3, sample: Point2) -> Option<LightSample>;
+
+    /// Shadow rays cast towards the light from each shaded point, more rays
+    /// give smoother penumbrae
+    fn shadow_samples(&self) -> u32 {
+        1
+    }
+
+    /// Whether the light lights diffuse surfaces
+    fn diffuse(&self) -> bool;
+
+    /// Whether the light causes specular highlights
+    fn specular(&self) -> bool;
+
+    /// Whether samples carry the radiance the light emits, like that of
+    /// environments and emissive surfaces, rather than an intensity that
+    /// includes the 1 / π of diffuse reflection
+    fn emits_radiance(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::math::Rng;
+
+    /// The light a white diffuse surface at `point` facing up reflects,
+    /// estimated from light samples like direct lighting does
+    pub(crate) fn reflected(light: &dyn Light, point: Point3, seed: u64) -> f32 {
+        let mut rng = Rng::new(seed);
+        let samples = 20_000;
+        let scale = match light.emits_radiance() {
+            true => std::f32::consts::FRAC_1_PI,
+            false => 1.0,
+        };
+        let total: f32 = (0..samples)
+            .filter_map(|_| light.sample(point, Point2::new(rng.next_f32(), rng.next_f32())))
+            .map(|sample| sample.radiance.r() * sample.direction.y.max(0.0) / sample.pdf)
+            .sum();
+
+        total * scale / samples as f32
+    }
 }
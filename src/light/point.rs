@@ -1,10 +1,7 @@
 use crate::color::Color;
-use crate::intersection::Intersection;
-use crate::material::Material;
-use crate::math::Point3;
-use crate::ray::Ray;
+use crate::math::{Point2, Point3};
 
-use super::{Falloff, Light};
+use super::{Falloff, Light, LightSample};
 
 pub struct Point {
     pub origin: Point3,
@@ -35,82 +32,28 @@ impl Point {
     }
 
     pub fn intensity(&self, distance_to_light: f32) -> f32 {
-        match self.falloff {
-            Falloff::InverseSquare => {
-                1.0 / (distance_to_light * distance_to_light) * self.intensity
-            }
-            Falloff::InverseLinear => 1.0 / distance_to_light * self.intensity,
-        }
+        self.falloff.attenuate(self.intensity, distance_to_light)
     }
 }
 
 impl Light for Point {
-    fn create_shadow_ray(
-        &self,
-        intersection: &Intersection,
-        medium_refraction: Option<f32>,
-    ) -> Ray {
-        let light_direction = (self.origin - intersection.point).normalize();
-        Ray::new(
-            (intersection.point + light_direction * 1e-3).as_point(),
-            light_direction,
-            medium_refraction,
-        )
+    fn sample(&self, point: Point3, _sample: Point2) -> Option<LightSample> {
+        let distance = (self.origin - point).length();
+
+        Some(LightSample {
+            point: self.origin,
+            direction: (self.origin - point).normalize(),
+            distance,
+            pdf: 1.0,
+            radiance: self.color * self.intensity(distance),
+        })
     }
 
-    fn distance_to_light(&self, intersection: &Intersection) -> f32 {
-        (intersection.point - self.origin).length()
+    fn diffuse(&self) -> bool {
+        self.diffuse
     }
 
-    fn diffuse_color(
-        &self,
-        intersection: &Intersection,
-        material: &Material,
-        distance_to_light: f32,
-    ) -> Option<Color> {
-        if !self.diffuse {
-            return None;
-        }
-
-        let light_direction = (self.origin - intersection.point).normalize();
-        let dot = light_direction.dot(&intersection.normal);
-
-        if dot > 0.0 {
-            Some(
-                (self.color * material.diffuse_color(intersection.texture_coord))
-                    * dot
-                    * self.intensity(distance_to_light),
-            )
-        } else {
-            None
-        }
-    }
-
-    fn specular_color(
-        &self,
-        intersection: &Intersection,
-        material: &Material,
-        ray: &Ray,
-        distance_to_light: f32,
-    ) -> Option<Color> {
-        if !self.specular {
-            return None;
-        }
-
-        let light_direction = (self.origin - intersection.point).normalize();
-        let dot = ray
-            .direction
-            .dot(&light_direction.reflect(&intersection.normal));
-
-        if dot > 0.0 {
-            let spec = dot.powf(material.specular_exponent);
-            Some(
-                (self.color * material.specular_color(intersection.texture_coord))
-                    * spec
-                    * self.intensity(distance_to_light),
-            )
-        } else {
-            None
-        }
+    fn specular(&self) -> bool {
+        self.specular
     }
 }
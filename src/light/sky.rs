@@ -0,0 +1,354 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use serde::Deserialize;
+
+use crate::color::Color;
+use crate::math::{Point2, Vector3};
+
+use super::environment::equirectangular_direction;
+use super::{Directional, Environment};
+
+/// Resolution of the image the sky is rendered into for sampling
+const SKY_WIDTH: usize = 512;
+const SKY_HEIGHT: usize = 256;
+
+/// Maps luminance in kcd/m² to the renderer's units, white surfaces in full
+/// sun then stay just below 1 without adjusting the exposure. Physical
+/// cameras use it to meter the scene.
+pub(crate) const LUMINANCE_SCALE: f32 = 0.02;
+
+/// Illuminance of the sun outside of the atmosphere, in klx
+const SOLAR_ILLUMINANCE: f32 = 128.0;
+
+/// How much of the sky's light the ground below the horizon reflects, it
+/// stands in for the ground in scenes that don't model one
+const GROUND_ALBEDO: f32 = 0.3;
+
+/// The sky fades to black while the sun sinks this far below the horizon,
+/// the end of civil twilight
+const TWILIGHT: f32 = 6.0 * PI / 180.0;
+
+/// Where the sun is in the sky. Azimuths are measured from north, along the
+/// negative z axis, towards east, along the positive x axis.
+#[derive(Deserialize, Debug, Copy, Clone)]
+#[serde(tag = "type")]
+pub enum SunPosition {
+    /// Angles in radians, the elevation is above the horizon
+    Angles { elevation: f32, azimuth: f32 },
+    /// The sun as seen from `latitude` and `longitude`, in degrees north and
+    /// east, at `time` hours local time in a time zone `timezone` hours ahead
+    /// of UTC
+    Location {
+        latitude: f32,
+        longitude: f32,
+        month: u32,
+        day: u32,
+        time: f32,
+        timezone: Option<f32>,
+    },
+}
+
+impl SunPosition {
+    /// Unit vector towards the sun
+    pub fn direction(&self) -> Vector3 {
+        let (elevation, azimuth) = match *self {
+            SunPosition::Angles { elevation, azimuth } => (elevation, azimuth),
+            SunPosition::Location {
+                latitude,
+                longitude,
+                month,
+                day,
+                time,
+                timezone,
+            } => solar_position(
+                latitude.to_radians(),
+                longitude,
+                day_of_year(month, day),
+                time - timezone.unwrap_or(0.0),
+            ),
+        };
+
+        Vector3::new(
+            azimuth.sin() * elevation.cos(),
+            elevation.sin(),
+            -azimuth.cos() * elevation.cos(),
+        )
+    }
+}
+
+/// The Preetham et al. 1999 analytic daylight model, a clear sky for a sun
+/// direction and a turbidity. Turbidity is the haziness of the atmosphere,
+/// from 2 for a very clear sky to 10 for a hazy one.
+pub struct Sky {
+    sun_direction: Vector3,
+    turbidity: f32,
+    /// Perez coefficients A to E for Y, x and y
+    perez: [[f32; 5]; 3],
+    /// Y in kcd/m², x and y at the zenith
+    zenith: [f32; 3],
+    /// Scales the sky down after sunset
+    twilight: f32,
+}
+
+impl Sky {
+    pub fn new(sun_direction: Vector3, turbidity: f32) -> Self {
+        let sun_direction = sun_direction.normalize();
+        let t = turbidity.clamp(1.7, 10.0);
+        // The model only covers suns above the horizon
+        let theta_sun = sun_direction.y.clamp(0.0, 1.0).acos();
+        let elevation = sun_direction.y.clamp(-1.0, 1.0).asin();
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let zenith_luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.0);
+        let thetas = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.0];
+        let chromaticity = |coefficients: [[f32; 4]; 3]| {
+            let [t2, t1, t0] = coefficients.map(|row| {
+                row.iter()
+                    .zip(thetas)
+                    .map(|(coefficient, theta)| coefficient * theta)
+                    .sum::<f32>()
+            });
+
+            t * t * t2 + t * t1 + t0
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        Self {
+            sun_direction,
+            turbidity: t,
+            perez: [
+                [
+                    0.1787 * t - 1.4630,
+                    -0.3554 * t + 0.4275,
+                    -0.0227 * t + 5.3251,
+                    0.1206 * t - 2.5771,
+                    -0.0670 * t + 0.3703,
+                ],
+                [
+                    -0.0193 * t - 0.2592,
+                    -0.0665 * t + 0.0008,
+                    -0.0004 * t + 0.2125,
+                    -0.0641 * t - 0.8989,
+                    -0.0033 * t + 0.0452,
+                ],
+                [
+                    -0.0167 * t - 0.2608,
+                    -0.0950 * t + 0.0092,
+                    -0.0079 * t + 0.2102,
+                    -0.0441 * t - 1.6537,
+                    -0.0109 * t + 0.0529,
+                ],
+            ],
+            zenith: [zenith_luminance, zenith_x, zenith_y],
+            twilight: (1.0 + elevation / TWILIGHT).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Radiance of the sky in `direction`, without the sun. Below the horizon
+    /// the ground reflects some of the sky at the horizon.
+    pub fn radiance(&self, direction: Vector3) -> Color {
+        let direction = direction.normalize();
+        if direction.y < 0.0 {
+            let horizon = Vector3::new(direction.x, 0.0, direction.z);
+            if horizon.length() <= 0.0 {
+                return Color::black();
+            }
+
+            return self.radiance(horizon) * GROUND_ALBEDO;
+        }
+
+        let cos_theta = direction.y.max(1e-3);
+        let cos_gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0);
+        let gamma = cos_gamma.acos();
+        let theta_sun = self.sun_direction.y.clamp(0.0, 1.0).acos();
+
+        let [luminance, x, y] = [0, 1, 2].map(|index| {
+            let [a, b, c, d, e] = self.perez[index];
+            let perez = |cos_theta: f32, gamma: f32, cos_gamma: f32| {
+                (1.0 + a * (b / cos_theta).exp())
+                    * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+            };
+
+            self.zenith[index] * perez(cos_theta, gamma, cos_gamma)
+                / perez(1.0, theta_sun, theta_sun.cos())
+        });
+
+        Color::from_xyy(x, y, luminance * LUMINANCE_SCALE * self.twilight)
+    }
+
+    /// The sun as a directional light, tinted by the atmosphere it shines
+    /// through. `None` once the sun has set.
+    pub fn sun(&self, intensity: f32) -> Option<Directional> {
+        if self.sun_direction.y <= 0.0 {
+            return None;
+        }
+
+        Some(Directional::new(
+            -self.sun_direction,
+            self.sun_transmittance(),
+            SOLAR_ILLUMINANCE / PI * LUMINANCE_SCALE * intensity,
+            true,
+            true,
+        ))
+    }
+
+    /// The sky as an environment light
+    pub fn environment(&self, intensity: f32, samples: u32, background: bool) -> Environment {
+        let pixels = (0..SKY_WIDTH * SKY_HEIGHT)
+            .map(|index| {
+                let uv = Point2::new(
+                    ((index % SKY_WIDTH) as f32 + 0.5) / SKY_WIDTH as f32,
+                    ((index / SKY_WIDTH) as f32 + 0.5) / SKY_HEIGHT as f32,
+                );
+
+                self.radiance(equirectangular_direction(uv))
+            })
+            .collect();
+
+        Environment::new(
+            pixels, SKY_WIDTH, SKY_HEIGHT, 0.0, intensity, samples, background,
+        )
+    }
+
+    /// The fraction of sunlight that makes it through Rayleigh and aerosol
+    /// scattering for red, green and blue wavelengths, from the appendix of
+    /// Preetham et al.
+    fn sun_transmittance(&self) -> Color {
+        let theta_sun = self.sun_direction.y.clamp(0.0, 1.0).acos();
+        let relative_air_mass =
+            1.0 / (theta_sun.cos() + 0.15 * (93.885 - theta_sun.to_degrees()).powf(-1.253));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = |wavelength: f32| {
+            let rayleigh = (-0.008735 * wavelength.powf(-4.08) * relative_air_mass).exp();
+            let aerosol = (-beta * wavelength.powf(-1.3) * relative_air_mass).exp();
+
+            rayleigh * aerosol
+        };
+
+        // Wavelengths in micrometers
+        Color::new(
+            transmittance(0.65),
+            transmittance(0.55),
+            transmittance(0.45),
+        )
+    }
+}
+
+/// Elevation and azimuth of the sun, in radians, at `latitude` radians and
+/// `longitude` degrees east. Uses NOAA's approximation of the equation of
+/// time and the solar declination.
+fn solar_position(latitude: f32, longitude: f32, day_of_year: u32, utc_hours: f32) -> (f32, f32) {
+    let year_angle = 2.0 * PI / 365.0 * (day_of_year as f32 - 1.0 + (utc_hours - 12.0) / 24.0);
+    let (sin1, cos1) = year_angle.sin_cos();
+    let (sin2, cos2) = (2.0 * year_angle).sin_cos();
+    let (sin3, cos3) = (3.0 * year_angle).sin_cos();
+
+    // In minutes
+    let equation_of_time =
+        229.18 * (0.000075 + 0.001868 * cos1 - 0.032077 * sin1 - 0.014615 * cos2 - 0.040849 * sin2);
+    let declination = 0.006918 - 0.399912 * cos1 + 0.070257 * sin1 - 0.006758 * cos2
+        + 0.000907 * sin2
+        - 0.002697 * cos3
+        + 0.00148 * sin3;
+
+    let solar_minutes = utc_hours * 60.0 + equation_of_time + 4.0 * longitude;
+    let hour_angle = (solar_minutes / 4.0 - 180.0).to_radians();
+
+    let cos_zenith = (latitude.sin() * declination.sin()
+        + latitude.cos() * declination.cos() * hour_angle.cos())
+    .clamp(-1.0, 1.0);
+    // Measured from south towards west, turned around to start at north
+    let azimuth = hour_angle
+        .sin()
+        .atan2(hour_angle.cos() * latitude.sin() - declination.tan() * latitude.cos())
+        + PI;
+
+    (FRAC_PI_2 - cos_zenith.acos(), azimuth)
+}
+
+/// Ignores leap years
+fn day_of_year(month: u32, day: u32) -> u32 {
+    const DAYS_BEFORE_MONTH: [u32; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+
+    DAYS_BEFORE_MONTH[(month.clamp(1, 12) - 1) as usize] + day
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elevation(direction: Vector3) -> f32 {
+        direction.y.asin().to_degrees()
+    }
+
+    #[test]
+    fn test_sun_positions() {
+        // Noon at the equator on the March equinox, the sun is overhead
+        let equinox = SunPosition::Location {
+            latitude: 0.0,
+            longitude: 0.0,
+            month: 3,
+            day: 20,
+            time: 12.0,
+            timezone: None,
+        };
+        assert!(elevation(equinox.direction()) > 85.0);
+
+        // Stockholm at 18:00 local summer time in June, the sun is low in the
+        // north west
+        let evening = SunPosition::Location {
+            latitude: 59.3,
+            longitude: 18.1,
+            month: 6,
+            day: 21,
+            time: 18.0,
+            timezone: Some(2.0),
+        }
+        .direction();
+        assert!((20.0..30.0).contains(&elevation(evening)));
+        assert!(evening.x < 0.0 && evening.z < 0.0);
+
+        let east = SunPosition::Angles {
+            elevation: 0.0,
+            azimuth: FRAC_PI_2,
+        };
+        assert_eq_vector3!(east.direction(), Vector3::new(1.0, 0.0, 0.0), 1e-6);
+    }
+
+    #[test]
+    fn test_sky_is_brightest_around_the_sun() {
+        let sun = Vector3::new(0.0, 0.5, -1.0).normalize();
+        let sky = Sky::new(sun, 3.0);
+        let near_sun = sky.radiance(Vector3::new(0.1, 0.5, -1.0));
+        let away_from_sun = sky.radiance(Vector3::new(0.0, 0.5, 1.0));
+        let zenith = sky.radiance(Vector3::new(0.0, 1.0, 0.0));
+
+        assert!(near_sun.luminance() > away_from_sun.luminance());
+        assert!(zenith.b() > zenith.r(), "A clear sky is blue");
+        assert!(sky.radiance(Vector3::new(0.0, -1.0, 0.0)).is_black());
+    }
+
+    #[test]
+    fn test_setting_sun_is_redder() {
+        let sun_color = |elevation: f32| {
+            let sky = Sky::new(Vector3::new(0.0, elevation.sin(), -elevation.cos()), 3.0);
+            let color = sky.sun_transmittance();
+
+            color.r() / color.b()
+        };
+
+        assert!(sun_color(0.05) > sun_color(1.2));
+        assert!(Sky::new(Vector3::new(0.0, -0.2, -1.0), 3.0)
+            .sun(1.0)
+            .is_none());
+    }
+}
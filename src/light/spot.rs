@@ -0,0 +1,151 @@
+use crate::color::Color;
+use crate::math::{Point2, Point3, Vector3};
+
+use super::{Falloff, Light, LightSample};
+
+/// The shape of a spot light's cone. Angles are measured from the direction
+/// of the light to the edge of the cone, in radians.
+#[derive(Debug, Copy, Clone)]
+pub struct Cone {
+    /// Inside of this angle the light is at full intensity
+    pub inner_angle: f32,
+    /// Outside of this angle there is no light
+    pub outer_angle: f32,
+    /// Shapes the transition between the angles, higher values give a
+    /// narrower hot spot
+    pub falloff_exponent: f32,
+}
+
+/// A point light that only shines within a cone around `direction`
+pub struct Spot {
+    pub origin: Point3,
+    direction: Vector3,
+    pub color: Color,
+    intensity: f32,
+    falloff: Falloff,
+    cos_inner: f32,
+    cos_outer: f32,
+    falloff_exponent: f32,
+    diffuse: bool,
+    specular: bool,
+}
+
+impl Spot {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        origin: Point3,
+        direction: Vector3,
+        cone: Cone,
+        color: Color,
+        intensity: f32,
+        falloff: Falloff,
+        diffuse: bool,
+        specular: bool,
+    ) -> Self {
+        let outer_angle = cone.outer_angle.max(0.0);
+
+        Self {
+            origin,
+            direction: direction.normalize(),
+            color,
+            intensity,
+            falloff,
+            cos_inner: cone.inner_angle.clamp(0.0, outer_angle).cos(),
+            cos_outer: outer_angle.cos(),
+            falloff_exponent: cone.falloff_exponent,
+            diffuse,
+            specular,
+        }
+    }
+
+    /// How much of the light's intensity leaves it along `direction`, from 1
+    /// inside of the inner cone to 0 outside of the outer cone
+    pub fn cone_attenuation(&self, direction: Vector3) -> f32 {
+        let cos_angle = direction.dot(&self.direction);
+        if cos_angle >= self.cos_inner {
+            return 1.0;
+        }
+        if cos_angle <= self.cos_outer {
+            return 0.0;
+        }
+
+        let t = (cos_angle - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        let smooth = t * t * (3.0 - 2.0 * t);
+
+        smooth.powf(self.falloff_exponent)
+    }
+}
+
+impl Light for Spot {
+    fn sample(&self, point: Point3, _sample: Point2) -> Option<LightSample> {
+        let direction = (self.origin - point).normalize();
+        let attenuation = self.cone_attenuation(-direction);
+        if attenuation <= 0.0 {
+            return None;
+        }
+        let distance = (self.origin - point).length();
+
+        Some(LightSample {
+            point: self.origin,
+            direction,
+            distance,
+            pdf: 1.0,
+            radiance: self.color * (self.falloff.attenuate(self.intensity, distance) * attenuation),
+        })
+    }
+
+    fn diffuse(&self) -> bool {
+        self.diffuse
+    }
+
+    fn specular(&self) -> bool {
+        self.specular
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    fn spot(falloff_exponent: f32) -> Spot {
+        Spot::new(
+            Point3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            Cone {
+                inner_angle: PI / 8.0,
+                outer_angle: PI / 4.0,
+                falloff_exponent,
+            },
+            Color::white(),
+            1.0,
+            Falloff::InverseSquare,
+            true,
+            true,
+        )
+    }
+
+    #[test]
+    fn test_cone_attenuation() {
+        let light = spot(1.0);
+        let at_angle = |angle: f32| Vector3::new(angle.sin(), -angle.cos(), 0.0);
+
+        assert_eq!(light.cone_attenuation(at_angle(0.0)), 1.0);
+        assert_eq!(light.cone_attenuation(at_angle(PI / 3.0)), 0.0);
+
+        let halfway = light.cone_attenuation(at_angle(PI * 3.0 / 16.0));
+        assert!(halfway > 0.0 && halfway < 1.0);
+        assert!(spot(4.0).cone_attenuation(at_angle(PI * 3.0 / 16.0)) < halfway);
+    }
+
+    #[test]
+    fn test_points_outside_of_the_cone_are_unlit() {
+        let light = spot(1.0);
+        let center = Point2::new(0.5, 0.5);
+
+        assert!(light.sample(Point3::at_origin(), center).is_some());
+        assert!(light.sample(Point3::new(5.0, 0.0, 0.0), center).is_none());
+        assert!(light.sample(Point3::new(0.0, 2.0, 0.0), center).is_none());
+    }
+}
@@ -1,20 +1,32 @@
 use std::env;
 use std::f32::consts::PI;
-use std::path::Path;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use getopts::Options;
+use tracing::{debug, info, warn};
 
+use rusttracer::animation::Animation;
+use rusttracer::aov::{self, AovFormat};
+use rusttracer::camera::StereoLayout;
+use rusttracer::checkpoint::{self, Checkpoint};
 #[cfg(feature = "stats")]
 use rusttracer::geometry::triangle::stats;
+use rusttracer::renderer::Progressive;
+use rusttracer::scheduler::CancellationToken;
 use rusttracer::texture;
-use rusttracer::{Color, ConfigLoader, IllumninationModel, MaterialTemplate};
+use rusttracer::{
+    Color, Config, ConfigLoader, Framebuffer, IllumninationModel, MaterialTemplate, SuperSampling,
+};
+
+/// Seconds between checkpoints when the config doesn't say
+const DEFAULT_CHECKPOINT_INTERVAL: f32 = 300.0;
 
 #[cfg(feature = "stats")]
 fn print_triangle_stats() {
-    use tracing::info;
-
     let number_of_tests = stats::number_of_triangle_intersections();
     let number_of_hits = stats::number_of_triangle_hits();
 
@@ -26,6 +38,60 @@ fn print_triangle_stats() {
     );
 }
 
+/// Save an 8 bit image to `{stem}.png`, or the left and right halves to
+/// `{stem}_left.png` and `{stem}_right.png` when `separate_eyes` is set
+fn save_png(
+    stem: &str,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    color_type: image::ColorType,
+    separate_eyes: bool,
+) -> image::ImageResult<()> {
+    if !separate_eyes {
+        return image::save_buffer(format!("{}.png", stem), pixels, width, height, color_type);
+    }
+
+    let row_length = (width * color_type.bytes_per_pixel() as u32) as usize;
+    let (left, right): (Vec<_>, Vec<_>) = pixels
+        .chunks_exact(row_length)
+        .map(|row| row.split_at(row_length / 2))
+        .unzip();
+    for (eye, rows) in [("left", left), ("right", right)] {
+        image::save_buffer(
+            format!("{}_{}.png", stem, eye),
+            &rows.concat(),
+            width / 2,
+            height,
+            color_type,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Parse a frame range like `10-20`, or a single frame like `15`
+fn parse_frames(range: &str) -> Result<RangeInclusive<u32>, String> {
+    let parse = |frame: &str| {
+        frame
+            .trim()
+            .parse::<u32>()
+            .map_err(|error| format!("Invalid frame {:?} in --frames: {}", frame, error))
+    };
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (parse(start)?, parse(end)?),
+        None => {
+            let frame = parse(range)?;
+            (frame, frame)
+        }
+    };
+    if start > end {
+        return Err(format!("--frames {} ends before it starts", range));
+    }
+
+    Ok(start..=end)
+}
+
 fn print_usage(program: &str, opts: &Options) {
     let brief = format!("Usage: {} [options]", program);
     print!("{}", opts.usage(&brief));
@@ -44,6 +110,35 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         "benchmark",
         "Benchmark by rendering the scene multiple times",
     );
+    opts.optflag(
+        "s",
+        "sample-count-map",
+        "Also write an image of the number of samples taken per pixel",
+    );
+    opts.optopt(
+        "r",
+        "resume",
+        "continue the render saved in a checkpoint, requires the same config",
+        "CHECKPOINT",
+    );
+    opts.optopt("", "scene", "render the scene with this name", "NAME");
+    opts.optopt(
+        "",
+        "camera",
+        "render through the camera with this name",
+        "NAME",
+    );
+    opts.optflag(
+        "a",
+        "all-cameras",
+        "render through every camera in the config, one image each",
+    );
+    opts.optopt(
+        "f",
+        "frames",
+        "render these frames of the animation, like 10-20 or 15",
+        "RANGE",
+    );
     opts.optflag("h", "help", "prints this help menu");
 
     let matches = match opts.parse(&args[1..]) {
@@ -57,6 +152,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let benchmark = matches.opt_present("b");
+    let sample_count_map = matches.opt_present("s");
     let config_path = matches.opt_str("c").expect("No config provided");
 
     let template = MaterialTemplate::new(
@@ -72,7 +168,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         material.ambient_color = Color::white() * 0.05;
         material.diffuse_color = Color::white() * 0.0;
         material.ambient_texture = Some(Rc::new(texture::Procedural::new(|uv| {
-            Color::new_f32(
+            Color::new(
                 ((uv.x * 32.0 * PI).sin() + (uv.y * 32.0 * PI).cos() + 1.0) * 0.5,
                 0.0,
                 0.0,
@@ -86,33 +182,259 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     config_loader
         .register_named_texture("checkerboard", Rc::new(texture::Procedural::checkerboard()));
 
-    let (renderer, config) = config_loader.load_renderer_from_config(&config_path)?;
-    let camera_config = config.cameras.first().unwrap();
+    let scene_name = matches.opt_str("scene");
+    let camera_name = matches.opt_str("camera");
+    let all_cameras = matches.opt_present("a");
+    let resume = matches.opt_str("r");
+    if all_cameras && (camera_name.is_some() || resume.is_some()) {
+        return Err("--all-cameras can't be combined with --camera or --resume".into());
+    }
+
+    let mut config = Config::new_from_file(&config_path)?;
+    let animation = Animation::from_config(&config)?;
+    let frames: Vec<Option<u32>> = match (&animation, matches.opt_str("frames")) {
+        (None, None) => vec![None],
+        (None, Some(_)) => return Err("--frames requires a config with an animation".into()),
+        (Some(animation), None) => animation.frames.clone().map(Some).collect(),
+        (Some(animation), Some(range)) => {
+            let range = parse_frames(&range)?;
+            if range.start() < animation.frames.start() || range.end() > animation.frames.end() {
+                warn!(
+                    "Frames {:?} extend past the animation's frames {:?}",
+                    range, animation.frames
+                );
+            }
 
-    if benchmark {
-        for _ in 0..10 {
-            let _ = renderer.render(config.max_depth);
+            range.map(Some).collect()
         }
+    };
+    if animation.is_some() && resume.is_some() {
+        return Err("--resume can't be used with animations".into());
+    }
 
-        return Ok(());
+    // Checkpoints are only written between the passes of progressive renders
+    let progressive =
+        config.progressive.is_some() || config.checkpoint.is_some() || resume.is_some();
+    if progressive && matches!(config.super_sampling, SuperSampling::Adaptive { .. }) {
+        return Err(
+            "Adaptive super sampling can't be combined with progressive rendering or checkpoints"
+                .into(),
+        );
+    }
+    if progressive && config.progressive.is_none() {
+        info!("Rendering progressively to be able to write checkpoints");
     }
 
-    let buffer = renderer.render(config.max_depth);
+    let now = SystemTime::now();
+    let timestamp = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+    let base_path = Path::new(&config_path).parent().unwrap();
+
+    for frame in frames {
+        if let (Some(animation), Some(frame)) = (&animation, frame) {
+            info!("Rendering frame {}", frame);
+            animation.apply(&mut config, frame as f32)?;
+        }
+
+        // Animated objects move, so the scene is rebuilt for every frame. The
+        // loader keeps the meshes it read for earlier frames.
+        let mut renderer = config_loader.load_renderer(
+            &config,
+            base_path,
+            scene_name.as_deref(),
+            camera_name.as_deref(),
+        )?;
+        let (scene_index, _) = config.scene(scene_name.as_deref())?;
+        let cameras: Vec<_> = if all_cameras {
+            config.cameras.iter().enumerate().collect()
+        } else {
+            vec![config.camera(camera_name.as_deref())?]
+        };
+
+        if benchmark {
+            for _ in 0..10 {
+                let _ = renderer.render(config.max_depth);
+            }
+
+            return Ok(());
+        }
+
+        // Every camera renders the same scene, it is only built once per frame
+        for (camera_index, camera_config) in cameras {
+            let stem = if all_cameras {
+                let label = camera_config
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("camera{}", camera_index));
+                info!("Rendering camera {}", label);
+                config_loader.set_camera(&mut renderer, &config, camera_config, base_path)?;
+
+                match frame {
+                    Some(frame) => format!("images/{}/{}/frame_{:04}", timestamp, label, frame),
+                    None => format!("images/{}_{}", timestamp, label),
+                }
+            } else {
+                match frame {
+                    Some(frame) => format!("images/{}/frame_{:04}", timestamp, frame),
+                    None => format!("images/{}", timestamp),
+                }
+            };
+            if let Some(directory) = Path::new(&stem).parent() {
+                fs::create_dir_all(directory)?;
+            }
+
+            // Stereo renders with separate images are rendered side by side and split
+            // when saving
+            let separate_eyes = camera_config
+                .stereo
+                .as_ref()
+                .is_some_and(|stereo| stereo.layout == Some(StereoLayout::Separate));
+            let save_beauty = |framebuffer: &Framebuffer| {
+                save_png(
+                    &stem,
+                    &renderer.develop(framebuffer)[..],
+                    framebuffer.width(),
+                    framebuffer.height(),
+                    image::ColorType::Rgb8,
+                    separate_eyes,
+                )
+                .unwrap();
+            };
+
+            let checkpoint_path = match (&resume, &config.checkpoint) {
+                (Some(path), _) => Some(PathBuf::from(path)),
+                (None, Some(checkpoint)) => Some(match (&checkpoint.path, all_cameras) {
+                    (Some(path), false) => PathBuf::from(path),
+                    _ => PathBuf::from(format!("{}.checkpoint", stem)),
+                }),
+                (None, None) => None,
+            };
+
+            let framebuffer = if progressive {
+                let config_hash = checkpoint::config_hash(
+                    &fs::read_to_string(&config_path)?,
+                    scene_index,
+                    camera_index,
+                )?;
+                let resumed_state = match &resume {
+                    Some(path) => {
+                        let checkpoint = renderer.load_checkpoint(Path::new(path))?;
+                        if checkpoint.config_hash != config_hash {
+                            return Err(format!(
+                                "The config has changed since {} was written, refusing to resume",
+                                path
+                            )
+                            .into());
+                        }
+                        info!(
+                            "Resuming from {} after {} samples per pixel",
+                            path, checkpoint.state.samples_per_pixel
+                        );
+
+                        Some(checkpoint.state)
+                    }
+                    None => None,
+                };
+
+                let mut settings = config
+                    .progressive
+                    .as_ref()
+                    .map(Progressive::from)
+                    .unwrap_or_default();
+                settings.checkpoint_every = checkpoint_path.as_ref().map(|_| {
+                    Duration::from_secs_f32(
+                        config
+                            .checkpoint
+                            .as_ref()
+                            .and_then(|checkpoint| checkpoint.interval_seconds)
+                            .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL),
+                    )
+                });
+
+                let state = renderer.render_progressive(
+                    config.max_depth,
+                    &settings,
+                    resumed_state,
+                    &CancellationToken::new(),
+                    |update, state| {
+                        info!(
+                            "Pass {}, {} samples per pixel after {:.1}s",
+                            state.passes,
+                            state.samples_per_pixel,
+                            state.elapsed.as_secs_f32()
+                        );
+                        if update.image_due {
+                            save_beauty(&state.accumulation);
+                        }
+                        if let (Some(path), true) = (&checkpoint_path, update.checkpoint_due) {
+                            if let Err(error) = Checkpoint::save(path, config_hash, state) {
+                                warn!("Failed to write checkpoint {}: {}", path.display(), error);
+                            }
+                        }
+                    },
+                );
+
+                state.accumulation
+            } else {
+                let framebuffer = renderer.render_tiles(
+                    config.max_depth,
+                    &CancellationToken::new(),
+                    |progress, _| {
+                        debug!(
+                            "Finished tile at ({}, {}), {:.1}% done",
+                            progress.tile.x,
+                            progress.tile.y,
+                            progress.fraction() * 100.0
+                        );
+                    },
+                );
+                save_beauty(&framebuffer);
+
+                framebuffer
+            };
+
+            if let Some(aovs) = &config.aovs {
+                match aovs.format.unwrap_or(AovFormat::Separate) {
+                    AovFormat::Separate => {
+                        for (index, aov) in renderer.aovs().iter().enumerate() {
+                            save_png(
+                                &format!("{}_{}", stem, aov.name()),
+                                &renderer.develop_aov(&framebuffer, index)[..],
+                                framebuffer.width(),
+                                framebuffer.height(),
+                                image::ColorType::Rgb8,
+                                separate_eyes,
+                            )
+                            .unwrap();
+                        }
+                    }
+                    AovFormat::Multilayer => {
+                        let filename = format!("{}.exr", stem);
+                        aov::write_multilayer(
+                            Path::new(&filename),
+                            &framebuffer,
+                            &renderer.beauty(&framebuffer),
+                            renderer.aovs(),
+                        )?;
+                    }
+                }
+            }
+
+            if sample_count_map {
+                save_png(
+                    &format!("{}_samples", stem),
+                    &framebuffer.sample_count_image()[..],
+                    framebuffer.width(),
+                    framebuffer.height(),
+                    image::ColorType::L8,
+                    separate_eyes,
+                )
+                .unwrap();
+            }
+        }
+    }
 
     #[cfg(feature = "stats")]
     print_triangle_stats();
 
-    let now = SystemTime::now();
-    let timestamp = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
-    let filename = format!("images/{}.png", timestamp);
-    image::save_buffer(
-        Path::new(&filename),
-        &buffer[..],
-        camera_config.width,
-        camera_config.height,
-        image::ColorType::Rgb8,
-    )
-    .unwrap();
-
     Ok(())
 }
@@ -94,6 +94,16 @@ pub struct Material {
     pub illumination_model: IllumninationModel,
     pub reflection_coefficient: Option<f32>,
     pub refraction_coefficient: Option<f32>,
+    /// Light the surface gives off itself, black for surfaces that don't glow
+    pub emission_color: Color,
+    pub emission_texture: OptionalTexture,
+    /// Scales the emission, to make surfaces brighter than white
+    pub emission_strength: f32,
+    /// Opacity from 0 for invisible surfaces to 1 for solid ones, like the
+    /// MTL `d` statement. Only shadow rays take it into account.
+    pub dissolve: f32,
+    /// Grayscale mask the dissolve is multiplied by, for cutouts like leaves
+    pub dissolve_texture: OptionalTexture,
 }
 
 impl Material {
@@ -130,6 +140,11 @@ impl Material {
             illumination_model,
             reflection_coefficient,
             refraction_coefficient,
+            emission_color: Color::black(),
+            emission_texture: None,
+            emission_strength: 1.0,
+            dissolve: 1.0,
+            dissolve_texture: None,
         }
     }
 
@@ -157,6 +172,11 @@ impl Material {
             illumination_model,
             reflection_coefficient,
             refraction_coefficient,
+            emission_color: Color::black(),
+            emission_texture: None,
+            emission_strength: 1.0,
+            dissolve: 1.0,
+            dissolve_texture: None,
         }
     }
 
@@ -186,6 +206,40 @@ impl Material {
             }),
         }
     }
+
+    /// The radiance the surface emits at `uv`
+    pub fn emission(&self, uv: Option<TextureCoord>) -> Color {
+        let color = match &self.emission_texture {
+            None => self.emission_color,
+            Some(texture) => uv.map_or(self.emission_color, |coord| {
+                self.emission_color * texture.lookup(coord)
+            }),
+        };
+
+        color * self.emission_strength
+    }
+
+    /// How much of the light reaching the surface at `uv` it stops, light
+    /// passing through refractive materials is handled separately
+    pub fn opacity(&self, uv: Option<TextureCoord>) -> f32 {
+        match &self.dissolve_texture {
+            None => self.dissolve,
+            Some(texture) => uv.map_or(self.dissolve, |coord| {
+                self.dissolve * texture.lookup(coord).average()
+            }),
+        }
+    }
+
+    /// Whether shadow rays pass straight through the surface at `uv` as if it
+    /// wasn't there, which is the case where it's fully transparent.
+    /// Refractive surfaces let light through by refraction instead.
+    pub fn is_cut_out(&self, uv: Option<TextureCoord>) -> bool {
+        !self.is_refractive() && self.opacity(uv) <= 0.0
+    }
+
+    pub fn is_emissive(&self) -> bool {
+        !self.emission_color.is_black() && self.emission_strength > 0.0
+    }
 }
 
 pub struct MaterialTemplate {
@@ -49,12 +49,16 @@ macro_rules! assert_eq_point3 {
 
 mod complex;
 mod matrix4;
+mod random;
+mod sampling;
 mod three_dimensions;
 mod transform;
 mod two_dimensions;
 
 pub use self::complex::Complex;
 pub use self::matrix4::Matrix4;
+pub use self::random::{hash_all, Rng};
+pub use self::sampling::{concentric_disk, Distribution1D, Distribution2D};
 pub use self::three_dimensions::{Point3, Vector3};
 pub use self::transform::Transform;
 pub use self::two_dimensions::Point2;
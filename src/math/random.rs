@@ -0,0 +1,111 @@
+// A small PCG32 generator, see https://www.pcg-random.org/. Rendering only
+// needs fast, reproducible streams of uniform numbers so there's no need to
+// pull in a full blown random number crate.
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+const DEFAULT_STREAM: u64 = 1_442_695_040_888_963_407;
+
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+    increment: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self::new_with_stream(seed, DEFAULT_STREAM)
+    }
+
+    pub fn new_with_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old_state = self.state;
+        self.state = old_state
+            .wrapping_mul(MULTIPLIER)
+            .wrapping_add(self.increment);
+
+        let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rotation = (old_state >> 59) as u32;
+
+        xor_shifted.rotate_right(rotation)
+    }
+
+    /// A uniformly distributed value in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        // Use the upper 24 bits, that's all the precision an f32 mantissa has
+        (self.next_u32() >> 8) as f32 * (1.0 / (1 << 24) as f32)
+    }
+}
+
+/// Scrambles the bits of `value`, this is the finalizer from splitmix64.
+fn hash(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    z ^ (z >> 31)
+}
+
+/// Hash a sequence of values into a single well distributed value.
+pub fn hash_all(values: &[u64]) -> u64 {
+    values.iter().fold(0x2545_f491_4f6c_dd1d, |seed, value| {
+        hash(seed ^ hash(*value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_all, Rng};
+
+    #[test]
+    fn test_deterministic() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+
+        for _ in 0..100 {
+            assert_eq!(a.next_u32(), b.next_u32());
+        }
+    }
+
+    #[test]
+    fn test_streams_differ() {
+        let mut a = Rng::new_with_stream(42, 1);
+        let mut b = Rng::new_with_stream(42, 2);
+
+        let a_values: Vec<_> = (0..8).map(|_| a.next_u32()).collect();
+        let b_values: Vec<_> = (0..8).map(|_| b.next_u32()).collect();
+
+        assert_ne!(a_values, b_values);
+    }
+
+    #[test]
+    fn test_next_f32_range() {
+        let mut rng = Rng::new(7);
+        let mut sum = 0.0;
+
+        for _ in 0..10_000 {
+            let value = rng.next_f32();
+            assert!((0.0..1.0).contains(&value));
+            sum += value;
+        }
+
+        assert_eq_within_bound!(sum / 10_000.0, 0.5, 0.02);
+    }
+
+    #[test]
+    fn test_hash_all_is_order_dependent() {
+        assert_eq!(hash_all(&[1, 2, 3]), hash_all(&[1, 2, 3]));
+        assert_ne!(hash_all(&[1, 2, 3]), hash_all(&[3, 2, 1]));
+    }
+}
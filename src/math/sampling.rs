@@ -0,0 +1,164 @@
+use std::f32::consts::FRAC_PI_4;
+
+use super::Point2;
+
+/// Map the unit square onto the unit disk with Shirley and Chiu's concentric
+/// mapping, which keeps strata intact and distorts less than polar mapping.
+pub fn concentric_disk(u: Point2) -> Point2 {
+    let (x, y) = (2.0 * u.x - 1.0, 2.0 * u.y - 1.0);
+    if x == 0.0 && y == 0.0 {
+        return Point2::at_origin();
+    }
+
+    let (radius, theta) = if x.abs() > y.abs() {
+        (x, FRAC_PI_4 * (y / x))
+    } else {
+        (y, 2.0 * FRAC_PI_4 - FRAC_PI_4 * (x / y))
+    };
+
+    Point2::new(radius * theta.cos(), radius * theta.sin())
+}
+
+/// A piecewise constant distribution over [0, 1) with one bucket per value.
+#[derive(Debug, Clone)]
+pub struct Distribution1D {
+    values: Vec<f32>,
+    cdf: Vec<f32>,
+    integral: f32,
+}
+
+impl Distribution1D {
+    pub fn new(values: Vec<f32>) -> Self {
+        let count = values.len() as f32;
+        let mut cdf = Vec::with_capacity(values.len() + 1);
+        cdf.push(0.0);
+        for value in &values {
+            cdf.push(cdf[cdf.len() - 1] + value.max(0.0) / count);
+        }
+
+        let integral = cdf[cdf.len() - 1];
+        for (index, entry) in cdf.iter_mut().enumerate() {
+            *entry = if integral > 0.0 {
+                *entry / integral
+            } else {
+                // Sample uniformly when all values are zero
+                index as f32 / count
+            };
+        }
+
+        Self {
+            values,
+            cdf,
+            integral,
+        }
+    }
+
+    /// The average of the values
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    /// Map `u` in [0, 1) to a position in [0, 1) distributed proportionally
+    /// to the values. Returns the position, its density and the bucket it's
+    /// in.
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        // The last entry with a cdf at or below u
+        let bucket = self
+            .cdf
+            .partition_point(|&entry| entry <= u)
+            .saturating_sub(1)
+            .min(self.values.len() - 1);
+        let width = self.cdf[bucket + 1] - self.cdf[bucket];
+        let offset = if width > 0.0 {
+            (u - self.cdf[bucket]) / width
+        } else {
+            0.0
+        };
+        let pdf = if self.integral > 0.0 {
+            self.values[bucket].max(0.0) / self.integral
+        } else {
+            1.0
+        };
+
+        (
+            ((bucket as f32 + offset) / self.values.len() as f32).min(1.0 - f32::EPSILON),
+            pdf,
+            bucket,
+        )
+    }
+}
+
+/// A piecewise constant distribution over [0, 1)² built from a `width` ×
+/// `height` grid of values in row major order, the first row is y = 0.
+#[derive(Debug, Clone)]
+pub struct Distribution2D {
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(values: &[f32], width: usize, height: usize) -> Self {
+        debug_assert_eq!(values.len(), width * height);
+
+        let rows: Vec<_> = values
+            .chunks(width)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(rows.iter().map(Distribution1D::integral).collect());
+
+        Self { rows, marginal }
+    }
+
+    /// Map `u` to a point distributed proportionally to the values, returns
+    /// the point and its density.
+    pub fn sample(&self, u: Point2) -> (Point2, f32) {
+        let (y, row_pdf, row) = self.marginal.sample(u.y);
+        let (x, column_pdf, _) = self.rows[row].sample(u.x);
+
+        (Point2::new(x, y), row_pdf * column_pdf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concentric_disk_stays_in_disk() {
+        for i in 0..16 {
+            for j in 0..16 {
+                let u = Point2::new(i as f32 / 15.0, j as f32 / 15.0);
+                let p = concentric_disk(u);
+
+                assert!(p.x * p.x + p.y * p.y <= 1.0 + 1e-5, "{:?}", p);
+            }
+        }
+
+        assert_eq_point2!(
+            concentric_disk(Point2::new(1.0, 0.5)),
+            Point2::new(1.0, 0.0),
+            1e-6
+        );
+    }
+
+    #[test]
+    fn test_distribution_follows_values() {
+        let distribution = Distribution2D::new(&[0.0, 3.0, 1.0, 0.0], 2, 2);
+        let mut counts = [0; 4];
+
+        for i in 0..64 {
+            for j in 0..64 {
+                let u = Point2::new((i as f32 + 0.5) / 64.0, (j as f32 + 0.5) / 64.0);
+                let (p, pdf) = distribution.sample(u);
+                let cell = (p.y * 2.0) as usize * 2 + (p.x * 2.0) as usize;
+
+                assert!(pdf > 0.0);
+                counts[cell] += 1;
+            }
+        }
+
+        assert_eq!(counts[0], 0);
+        assert_eq!(counts[3], 0);
+        assert_eq!(counts[1], 3 * counts[2]);
+    }
+}
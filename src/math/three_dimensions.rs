@@ -107,6 +107,20 @@ impl Vector3 {
     pub fn as_point(&self) -> Point3 {
         Point3::new(self.x, self.y, self.z)
     }
+
+    /// Two unit vectors that together with `self`, which should be normalized,
+    /// form an orthonormal basis. See Duff et al. 2017, "Building an
+    /// Orthonormal Basis, Revisited".
+    pub fn orthonormal_basis(&self) -> (Vector3, Vector3) {
+        let sign = 1.0_f32.copysign(self.z);
+        let a = -1.0 / (sign + self.z);
+        let b = self.x * self.y * a;
+
+        (
+            Vector3::new(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            Vector3::new(b, sign + self.y * self.y * a, -self.y),
+        )
+    }
 }
 
 // Point3 specific
@@ -416,6 +430,24 @@ mod tests {
         assert_eq_vector3!(result, expected2, EPSILON);
     }
 
+    #[test]
+    fn test_orthonormal_basis() {
+        for normal in &[
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(1.0, 2.0, 3.0).normalize(),
+            Vector3::new(-0.3, 0.1, -0.9).normalize(),
+        ] {
+            let (tangent, bitangent) = normal.orthonormal_basis();
+
+            assert_eq_within_bound!(tangent.length(), 1.0, EPSILON);
+            assert_eq_within_bound!(bitangent.length(), 1.0, EPSILON);
+            assert_eq_within_bound!(tangent.dot(normal), 0.0, EPSILON);
+            assert_eq_within_bound!(bitangent.dot(normal), 0.0, EPSILON);
+            assert_eq_within_bound!(tangent.dot(&bitangent), 0.0, EPSILON);
+        }
+    }
+
     #[test]
     fn test_vector3_mul_simple() {
         let m = Matrix4::identity();
@@ -79,6 +79,11 @@ impl<'a, V: BoundingVolume, S: 'a + TriangleStorage<'a>> MeshLoader<V, S> {
         }
     }
 
+    /// The directory paths passed to the loader are relative to
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+
     pub fn load(
         &mut self,
         path: &Path,
@@ -293,20 +298,36 @@ impl<'a, V: BoundingVolume, S: 'a + TriangleStorage<'a>> MeshLoader<V, S> {
             let diffuse = m.diffuse.unwrap_or([0.0; 3]);
             let specular = m.specular.unwrap_or([0.0; 3]);
 
-            let mat = Rc::new(Material::new_with_textures(
-                Color::new_f32(ambient[0], ambient[1], ambient[2]),
+            let mut mat = Material::new_with_textures(
+                Color::new(ambient[0], ambient[1], ambient[2]),
                 ambient_texture,
-                Color::new_f32(diffuse[0], diffuse[1], diffuse[2]),
+                Color::new(diffuse[0], diffuse[1], diffuse[2]),
                 diffuse_texture,
-                Color::new_f32(specular[0], specular[1], specular[2]),
+                Color::new(specular[0], specular[1], specular[2]),
                 specular_texture,
                 m.shininess.unwrap_or(0.0),
                 illumination_model,
                 m.shininess.map(|s| 1000.0 / s),
                 m.optical_density,
-            ));
+            );
+            // Emission isn't part of the original MTL spec, tobj leaves it
+            // unparsed
+            mat.emission_texture = self
+                .load_texture_from_file(path, m.unknown_param.get("map_Ke").map(String::as_str))?;
+            match m
+                .unknown_param
+                .get("Ke")
+                .and_then(|value| parse_color(value))
+            {
+                Some(emission) => mat.emission_color = emission,
+                // A map on its own emits its colors as they are
+                None if mat.emission_texture.is_some() => mat.emission_color = Color::white(),
+                None => {}
+            }
+            mat.dissolve = m.dissolve.unwrap_or(1.0);
+            mat.dissolve_texture = self.load_mask_from_file(path, m.dissolve_texture.as_deref())?;
 
-            material_cache.insert(i, mat);
+            material_cache.insert(i, Rc::new(mat));
         }
 
         Ok(material_cache)
@@ -317,22 +338,53 @@ impl<'a, V: BoundingVolume, S: 'a + TriangleStorage<'a>> MeshLoader<V, S> {
         obj_path: &Path,
         texture: Option<&str>,
     ) -> Result<OptionalTexture, MeshLoadError> {
-        let Some(path) = texture else { return Ok(None) };
-        if path.is_empty() {
+        let Some(full_path) = Self::texture_path(obj_path, texture) else {
             return Ok(None);
-        }
-
-        let full_path = if let Some(resolve_path) = obj_path.parent() {
-            resolve_path.join(path)
-        } else {
-            PathBuf::from(path)
         };
         let texture = texture::file::File::new(full_path)?;
 
         Ok(Some(Rc::new(texture)))
     }
 
+    /// Like `load_texture_from_file` for masks, which aren't sRGB encoded
+    fn load_mask_from_file(
+        &self,
+        obj_path: &Path,
+        texture: Option<&str>,
+    ) -> Result<OptionalTexture, MeshLoadError> {
+        let Some(full_path) = Self::texture_path(obj_path, texture) else {
+            return Ok(None);
+        };
+        let texture = texture::file::File::new_linear(full_path)?;
+
+        Ok(Some(Rc::new(texture)))
+    }
+
+    fn texture_path(obj_path: &Path, texture: Option<&str>) -> Option<PathBuf> {
+        let path = texture.filter(|path| !path.is_empty())?;
+
+        Some(if let Some(resolve_path) = obj_path.parent() {
+            resolve_path.join(path)
+        } else {
+            PathBuf::from(path)
+        })
+    }
+
     fn build_cache_key(filename: &str, mesh_name: &str) -> String {
         format!("{}-{}", filename, mesh_name)
     }
 }
+
+/// Parse an MTL color of three whitespace separated components
+fn parse_color(value: &str) -> Option<Color> {
+    let components: Vec<f32> = value
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    match components[..] {
+        [r, g, b] => Some(Color::new(r, g, b)),
+        _ => None,
+    }
+}
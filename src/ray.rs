@@ -7,6 +7,9 @@ pub struct Ray {
     pub inv_direction: Vector3,
     pub sign: [usize; 3],
     pub medium_refraction: f32,
+    /// When the ray was sent, 0 is the start of the frame and 1 the start of
+    /// the next one
+    pub time: f32,
 }
 
 impl Ray {
@@ -22,6 +25,12 @@ impl Ray {
                 (inv_dir.z < 0.0) as usize,
             ],
             medium_refraction: medium_refraction.unwrap_or(1.0),
+            time: 0.0,
         }
     }
+
+    pub fn with_time(mut self, time: f32) -> Ray {
+        self.time = time;
+        self
+    }
 }
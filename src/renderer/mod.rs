@@ -0,0 +1,1076 @@
+mod path_tracer;
+mod progressive;
+
+use std::f32::consts::FRAC_1_PI;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use serde::Deserialize;
+
+use crate::aov::{self, Aov};
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::denoiser::{Denoiser, Features};
+use crate::filter::Filter;
+use crate::framebuffer::{AovFilter, Framebuffer};
+use crate::intersection::Intersection;
+use crate::material::{IllumninationModel, Material};
+use crate::math::{hash_all, Point2, Rng, Vector3};
+use crate::ray::Ray;
+use crate::sampler::{Grid, Sampler};
+use crate::scene::Scene;
+use crate::scheduler::{self, CancellationToken, Schedule, Tile, TileProgress};
+use crate::tone_mapping::ToneMapping;
+
+pub use self::progressive::{Progressive, ProgressiveState, ProgressiveUpdate};
+
+const RAY_OFFSET: f32 = 1e-3;
+
+/// Surfaces a shadow ray passes through before the light is considered blocked
+const MAX_SHADOW_SURFACES: usize = 16;
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub enum SuperSampling {
+    Off,
+    /// A fixed number of samples per pixel, the value is squared.
+    On(u32),
+    /// Take at least `min_samples` per pixel and keep sampling, up to
+    /// `max_samples`, while the estimated variance of the pixel's mean is
+    /// above `threshold`. The variance is measured on the tone mapped
+    /// luminance so the threshold doesn't depend on the scene's brightness.
+    Adaptive {
+        min_samples: u32,
+        max_samples: u32,
+        threshold: f32,
+    },
+}
+
+impl SuperSampling {
+    /// The largest number of samples that will be taken for a single pixel
+    pub fn max_samples_per_pixel(&self) -> u32 {
+        match *self {
+            SuperSampling::Off => 1,
+            SuperSampling::On(samples) => samples * samples,
+            SuperSampling::Adaptive {
+                min_samples,
+                max_samples,
+                ..
+            } => max_samples.max(min_samples).max(1),
+        }
+    }
+}
+
+/// The range of sample indices to take for a pixel. Sampling stops early once
+/// `min_samples` have been taken and the variance of the pixel's mean is
+/// below `threshold`.
+#[derive(Debug, Copy, Clone)]
+struct SampleBudget {
+    first_sample: u32,
+    min_samples: u32,
+    max_samples: u32,
+    threshold: f32,
+}
+
+impl SampleBudget {
+    fn fixed(first_sample: u32, samples: u32) -> Self {
+        Self {
+            first_sample,
+            min_samples: samples,
+            max_samples: samples,
+            threshold: 0.0,
+        }
+    }
+}
+
+/// Welford's online algorithm for the mean and variance of a sequence.
+#[derive(Debug, Default, Copy, Clone)]
+struct RunningVariance {
+    count: u32,
+    mean: f32,
+    sum_of_squares: f32,
+}
+
+impl RunningVariance {
+    fn add(&mut self, value: f32) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f32;
+        self.sum_of_squares += delta * (value - self.mean);
+    }
+
+    /// The estimated variance of the mean of the values seen so far
+    fn variance_of_mean(&self) -> f32 {
+        if self.count < 2 {
+            return f32::INFINITY;
+        }
+
+        self.sum_of_squares / ((self.count - 1) * self.count) as f32
+    }
+}
+
+/// The light transport algorithm used to compute the radiance along camera rays.
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub enum Integrator {
+    /// Classic Whitted style ray tracing. Direct lighting with perfect
+    /// reflections and refractions, indirect light is approximated with the
+    /// scene's ambient color.
+    Whitted,
+    /// Unbiased Monte Carlo path tracing. Every vertex samples the lights
+    /// directly and continues the path along a lobe of the same normalized
+    /// Phong BRDF, the noise goes away as the number of samples per pixel
+    /// goes up.
+    PathTracer,
+}
+
+/// How direct lighting shades specular highlights
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Specular {
+    None,
+    /// Classic Phong highlights
+    Phong,
+    /// The energy conserving Phong BRDF the path tracer samples glossy
+    /// bounces from
+    NormalizedPhong,
+}
+
+/// Radiance arriving at the camera split by how it left the first surface
+/// along the path, used for the lighting AOVs.
+#[derive(Debug, Copy, Clone)]
+struct Lighting {
+    direct: Color,
+    indirect: Color,
+    reflection: Color,
+    refraction: Color,
+}
+
+impl Lighting {
+    fn black() -> Self {
+        Self::direct(Color::black())
+    }
+
+    fn direct(color: Color) -> Self {
+        Self {
+            direct: color,
+            indirect: Color::black(),
+            reflection: Color::black(),
+            refraction: Color::black(),
+        }
+    }
+
+    fn total(&self) -> Color {
+        self.direct + self.indirect + self.reflection + self.refraction
+    }
+}
+
+/// How a renderer samples, integrates and outputs the image of a scene
+pub struct RenderSettings {
+    pub super_sampling: SuperSampling,
+    pub sampler: Box<dyn Sampler>,
+    pub filter: Filter,
+    pub integrator: Integrator,
+    pub tone_mapping: ToneMapping,
+    pub aovs: Vec<Aov>,
+    pub denoiser: Option<Denoiser>,
+    pub schedule: Schedule,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            super_sampling: SuperSampling::Off,
+            sampler: Box::new(Grid::new(1)),
+            filter: Filter::default(),
+            integrator: Integrator::Whitted,
+            tone_mapping: ToneMapping::default(),
+            aovs: vec![],
+            denoiser: None,
+            schedule: Schedule::default(),
+        }
+    }
+}
+
+pub struct Renderer {
+    scene: Scene,
+    camera: Box<dyn Camera>,
+    super_sampling: SuperSampling,
+    sampler: Box<dyn Sampler>,
+    filter: Filter,
+    integrator: Integrator,
+    tone_mapping: ToneMapping,
+    /// The AOVs that were asked for followed by the denoiser's feature
+    /// buffers that weren't, in framebuffer order
+    aovs: Vec<Aov>,
+    /// The number of AOVs that were asked for
+    output_aovs: usize,
+    denoiser: Option<Denoiser>,
+    schedule: Schedule,
+}
+
+pub struct RefractionProperties {
+    n1: f32,
+    n2: f32,
+    n: f32,
+    cos_i: f32,
+    c2: f32,
+    normal: Vector3,
+}
+
+impl RefractionProperties {
+    fn new(intersection: &Intersection, original_ray: &Ray) -> RefractionProperties {
+        let refraction_coefficient = if intersection.inside {
+            1.0
+        } else {
+            intersection
+                .shape
+                .material()
+                .refraction_coefficient
+                .unwrap_or(1.0)
+        };
+
+        let n = original_ray.medium_refraction / refraction_coefficient;
+        let normal = if intersection.inside {
+            -intersection.normal
+        } else {
+            intersection.normal
+        };
+
+        let cos_i = normal.dot(&original_ray.direction);
+        let c2 = 1.0 - n * n * (1.0 - cos_i * cos_i);
+
+        RefractionProperties {
+            n1: original_ray.medium_refraction,
+            n2: refraction_coefficient,
+            n,
+            cos_i,
+            c2,
+            normal,
+        }
+    }
+
+    fn total_internal_reflection(&self) -> bool {
+        self.c2 < 0.0
+    }
+}
+
+unsafe impl Sync for Renderer {}
+unsafe impl Send for Renderer {}
+
+impl Renderer {
+    pub fn new(scene: Scene, camera: Box<dyn Camera>, settings: RenderSettings) -> Renderer {
+        let RenderSettings {
+            super_sampling,
+            sampler,
+            filter,
+            integrator,
+            tone_mapping,
+            mut aovs,
+            denoiser,
+            schedule,
+        } = settings;
+
+        // The denoiser relies on these feature buffers, they are rendered but
+        // not output unless asked for
+        let output_aovs = aovs.len();
+        if denoiser.is_some() {
+            for feature in &[Aov::Albedo, Aov::Normal, Aov::Depth] {
+                if !aovs.contains(feature) {
+                    aovs.push(*feature);
+                }
+            }
+        }
+
+        Renderer {
+            scene,
+            camera,
+            super_sampling,
+            sampler,
+            filter,
+            integrator,
+            tone_mapping,
+            aovs,
+            output_aovs,
+            denoiser,
+            schedule,
+        }
+    }
+
+    /// Render through `camera` from now on, the scene is kept as is
+    pub fn set_camera(&mut self, camera: Box<dyn Camera>) {
+        self.camera = camera;
+    }
+
+    pub fn set_tone_mapping(&mut self, tone_mapping: ToneMapping) {
+        self.tone_mapping = tone_mapping;
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    /// The AOVs that were asked for, they come first in the framebuffer
+    pub fn aovs(&self) -> &[Aov] {
+        &self.aovs[..self.output_aovs]
+    }
+
+    pub fn render(&self, max_depth: u32) -> Vec<u8> {
+        self.develop(&self.render_framebuffer(max_depth))
+    }
+
+    /// Tone map and quantise a rendered framebuffer to 8 bit RGB.
+    pub fn develop(&self, framebuffer: &Framebuffer) -> Vec<u8> {
+        self.beauty(framebuffer)
+            .into_iter()
+            .flat_map(|color| self.tone_mapping.apply(color).to_rgb8())
+            .collect()
+    }
+
+    /// The final linear radiance of each pixel, denoised if enabled.
+    pub fn beauty(&self, framebuffer: &Framebuffer) -> Vec<Color> {
+        let (width, height) = (framebuffer.width(), framebuffer.height());
+        let resolve = |index: Option<usize>| {
+            (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| match index {
+                    Some(aov) => framebuffer.aov(aov, x, y),
+                    None => framebuffer.pixel(x, y),
+                })
+                .collect::<Vec<_>>()
+        };
+        let color = resolve(None);
+
+        match &self.denoiser {
+            None => color,
+            Some(denoiser) => {
+                let feature = |aov| resolve(self.aovs.iter().position(|a| *a == aov));
+                let (albedo, normal, depth) = (
+                    feature(Aov::Albedo),
+                    feature(Aov::Normal),
+                    feature(Aov::Depth),
+                );
+                let features = Features {
+                    albedo: &albedo,
+                    normal: &normal,
+                    depth: &depth,
+                };
+
+                denoiser.denoise(width, height, &color, &features)
+            }
+        }
+    }
+
+    /// Quantise the `index`-th AOV of a rendered framebuffer to 8 bit RGB.
+    pub fn develop_aov(&self, framebuffer: &Framebuffer, index: usize) -> Vec<u8> {
+        aov::develop(framebuffer, index, self.aovs[index], &self.tone_mapping)
+    }
+
+    /// Render the linear radiance of the scene into a framebuffer.
+    pub fn render_framebuffer(&self, max_depth: u32) -> Framebuffer {
+        self.render_tiles(max_depth, &CancellationToken::new(), |_, _| {})
+    }
+
+    /// Render the scene tile by tile, `on_tile` is called from the render
+    /// threads with the progress and the finished tile each time one
+    /// completes. When `cancellation` is triggered the remaining tiles are
+    /// skipped and the partially rendered framebuffer is returned.
+    pub fn render_tiles<F>(
+        &self,
+        max_depth: u32,
+        cancellation: &CancellationToken,
+        on_tile: F,
+    ) -> Framebuffer
+    where
+        F: Fn(&TileProgress, &Framebuffer) + Sync,
+    {
+        self.render_pass(max_depth, &self.sample_budget(), cancellation, on_tile)
+    }
+
+    fn render_pass<F>(
+        &self,
+        max_depth: u32,
+        budget: &SampleBudget,
+        cancellation: &CancellationToken,
+        on_tile: F,
+    ) -> Framebuffer
+    where
+        F: Fn(&TileProgress, &Framebuffer) + Sync,
+    {
+        let (width, height) = (self.camera.width(), self.camera.height());
+        // Samples are splatted into the pixels within the filter's radius
+        let margin = self.filter.radius().ceil() as u32;
+        let views = self.camera.views();
+        let tiles: Vec<Tile> = views
+            .iter()
+            .flat_map(|view| {
+                self.schedule
+                    .tiles(view.width, view.height)
+                    .into_iter()
+                    .map(move |tile| Tile {
+                        x: tile.x + view.x,
+                        y: tile.y + view.y,
+                        ..tile
+                    })
+            })
+            .collect();
+        let framebuffer = Mutex::new(self.new_framebuffer(0, 0, width, height));
+        let completed_tiles = AtomicUsize::new(0);
+
+        scheduler::for_each_tile(&tiles, cancellation, |tile| {
+            // Samples don't bleed across the edges of a view
+            let view = views
+                .iter()
+                .find(|view| {
+                    (view.x..view.x + view.width).contains(&tile.x)
+                        && (view.y..view.y + view.height).contains(&tile.y)
+                })
+                .expect("Every tile lies within a view");
+            let (x, y) = (
+                tile.x.saturating_sub(margin).max(view.x),
+                tile.y.saturating_sub(margin).max(view.y),
+            );
+            let mut region = self.new_framebuffer(
+                x,
+                y,
+                (tile.x + tile.width + margin).min(view.x + view.width) - x,
+                (tile.y + tile.height + margin).min(view.y + view.height) - y,
+            );
+
+            for pixel_y in tile.y..tile.y + tile.height {
+                for pixel_x in tile.x..tile.x + tile.width {
+                    self.render_point(max_depth, pixel_x, pixel_y, budget, &mut region);
+                }
+            }
+
+            framebuffer
+                .lock()
+                .expect("Another render thread panicked")
+                .merge(&region);
+
+            let progress = TileProgress {
+                tile,
+                completed_tiles: completed_tiles.fetch_add(1, Ordering::SeqCst) + 1,
+                total_tiles: tiles.len(),
+            };
+            on_tile(&progress, &region);
+        });
+
+        framebuffer
+            .into_inner()
+            .expect("Another render thread panicked")
+    }
+
+    /// An empty framebuffer for the AOVs of this renderer covering the region
+    /// with the top left corner at (`x`, `y`)
+    fn new_framebuffer(&self, x: u32, y: u32, width: u32, height: u32) -> Framebuffer {
+        let filters: Vec<AovFilter> = self.aovs.iter().map(Aov::filter).collect();
+
+        Framebuffer::new_region(x, y, width, height, self.aovs.len()).with_aov_filters(&filters)
+    }
+
+    /// The samples taken for every pixel when rendering a single frame
+    fn sample_budget(&self) -> SampleBudget {
+        match self.super_sampling {
+            SuperSampling::Off => SampleBudget::fixed(0, 1),
+            SuperSampling::On(samples) => SampleBudget::fixed(0, samples * samples),
+            SuperSampling::Adaptive {
+                min_samples,
+                threshold,
+                ..
+            } => SampleBudget {
+                first_sample: 0,
+                min_samples: min_samples.max(2),
+                max_samples: self.super_sampling.max_samples_per_pixel(),
+                threshold,
+            },
+        }
+    }
+
+    fn render_point(
+        &self,
+        max_depth: u32,
+        x: u32,
+        y: u32,
+        budget: &SampleBudget,
+        framebuffer: &mut Framebuffer,
+    ) {
+        let mut rng = Rng::new(hash_all(&[
+            u64::from(y * self.camera.width() + x),
+            u64::from(budget.first_sample),
+        ]));
+        let mut variance = RunningVariance::default();
+        let mut aov_values = vec![Color::black(); self.aovs.len()];
+        let shutter = self.camera.shutter();
+
+        for taken in 0..budget.max_samples {
+            let index = budget.first_sample + taken;
+            let offset = self.sampler.sample_2d(x, y, index, 0);
+            let time = shutter.time(self.sampler.sample_2d(x, y, index, 2).x);
+            // The camera's y axis points up, flip the sample to match
+            let ray = self
+                .camera
+                .create_ray(
+                    x,
+                    self.camera.height() - 1 - y,
+                    Point2::new(offset.x, 1.0 - offset.y),
+                    self.sampler.sample_2d(x, y, index, 1),
+                )
+                .map(|ray| ray.with_time(time));
+            // The integrators and the AOVs share the camera ray's intersection
+            let primary = ray.and_then(|ray| self.scene.intersect_object(ray, true));
+            let hit = primary.map(|(_, hit)| hit);
+            let lighting = match (ray, self.integrator) {
+                (None, _) => Lighting::black(),
+                (Some(ray), Integrator::Whitted) => self.shade(ray, hit, max_depth, true, &mut rng),
+                (Some(ray), Integrator::PathTracer) => {
+                    self.trace_path(ray, primary, max_depth, &mut rng)
+                }
+            };
+            let result = lighting.total();
+            self.evaluate_aovs(ray, primary.as_ref(), &lighting, &mut aov_values);
+
+            framebuffer.add_sample(
+                Point2::new(x as f32 + offset.x, y as f32 + offset.y),
+                result,
+                &aov_values,
+                &self.filter,
+            );
+
+            variance.add(self.tone_mapping.apply(result).luminance());
+            if taken + 1 >= budget.min_samples && variance.variance_of_mean() <= budget.threshold {
+                break;
+            }
+        }
+    }
+
+    fn evaluate_aovs(
+        &self,
+        ray: Option<Ray>,
+        primary: Option<&(usize, Intersection)>,
+        lighting: &Lighting,
+        values: &mut [Color],
+    ) {
+        for (value, aov) in values.iter_mut().zip(&self.aovs) {
+            *value = match (aov, primary) {
+                (Aov::Direct, _) => lighting.direct,
+                (Aov::Indirect, _) => lighting.indirect,
+                (Aov::Reflection, _) => lighting.reflection,
+                (Aov::Refraction, _) => lighting.refraction,
+                (_, None) => Color::black(),
+                (Aov::Depth, Some((_, hit))) => {
+                    Color::white() * ray.map_or(0.0, |ray| self.camera.depth(&ray, hit.point))
+                }
+                (Aov::Normal, Some((_, hit))) => {
+                    Color::new(hit.normal.x, hit.normal.y, hit.normal.z)
+                }
+                (Aov::Albedo, Some((_, hit))) => {
+                    hit.shape.material().diffuse_color(hit.texture_coord)
+                }
+                (Aov::Uv, Some((_, hit))) => hit
+                    .texture_coord
+                    .map_or(Color::black(), |uv| Color::new(uv.x, uv.y, 0.0)),
+                (Aov::ObjectId, Some((object, _))) => aov::id_color(*object as u64 + 1),
+                (Aov::MaterialId, Some((_, hit))) => {
+                    aov::id_color(aov::material_id(hit.shape.material()))
+                }
+            };
+        }
+    }
+
+    fn trace(&self, ray: Ray, depth: u32, cull: bool, rng: &mut Rng) -> Lighting {
+        if depth == 0 {
+            return Lighting::black();
+        }
+
+        self.shade(ray, self.scene.intersect(ray, cull), depth, cull, rng)
+    }
+
+    /// Whitted style shading of `ray`'s intersection `hit`
+    fn shade(
+        &self,
+        ray: Ray,
+        hit: Option<Intersection>,
+        depth: u32,
+        cull: bool,
+        rng: &mut Rng,
+    ) -> Lighting {
+        if depth == 0 {
+            return Lighting::black();
+        }
+
+        let Some(hit) = hit else {
+            // Only camera rays are culled
+            return Lighting::direct(self.scene.background(&ray, cull));
+        };
+        let material = hit.shape.material();
+
+        if material.illumination_model == IllumninationModel::Constant {
+            return Lighting::direct(material.diffuse_color(hit.texture_coord));
+        }
+
+        let specular = match material.illumination_model {
+            IllumninationModel::Diffuse => Specular::None,
+            _ => Specular::Phong,
+        };
+        let mut lighting = Lighting {
+            direct: self.direct_lighting(&hit, ray, specular, rng)
+                + material.emission(hit.texture_coord),
+            indirect: material.ambient_color(hit.texture_coord) * self.scene.ambient_color,
+            reflection: Color::black(),
+            refraction: Color::black(),
+        };
+
+        match material.illumination_model {
+            IllumninationModel::Constant
+            | IllumninationModel::Diffuse
+            | IllumninationModel::DiffuseSpecular => {}
+            IllumninationModel::DiffuseSpecularReflective
+            | IllumninationModel::DiffuseSpecularReflectiveGlass
+            | IllumninationModel::DiffuseSpecularFresnel => {
+                lighting.reflection = self.reflect(&hit, ray, depth, rng);
+            }
+            IllumninationModel::DiffuseSpecularRefracted => {
+                let refraction_properties = RefractionProperties::new(&hit, &ray);
+
+                lighting.reflection = self.reflect(&hit, ray, depth, rng);
+                if !refraction_properties.total_internal_reflection() {
+                    lighting.refraction =
+                        self.refract(&hit, ray, depth, &refraction_properties, rng);
+                }
+            }
+            IllumninationModel::DiffuseSpecularRefractedFresnel => {
+                let refraction_properties = RefractionProperties::new(&hit, &ray);
+                let kr = self.fresnel(&refraction_properties);
+                let kt = 1.0 - kr;
+
+                lighting.reflection = self.reflect(&hit, ray, depth, rng) * kr;
+                if !refraction_properties.total_internal_reflection() {
+                    lighting.refraction =
+                        self.refract(&hit, ray, depth, &refraction_properties, rng) * kt;
+                }
+            }
+        }
+
+        lighting
+    }
+
+    fn direct_lighting(
+        &self,
+        intersection: &Intersection,
+        original_ray: Ray,
+        specular: Specular,
+        rng: &mut Rng,
+    ) -> Color {
+        let material: &Material = intersection.shape.material();
+        let mut result = Color::black();
+
+        for light in self.scene.lights() {
+            let samples = light.shadow_samples();
+            let mut light_result = Color::black();
+            // See `LightSample::radiance`, the unnormalized Phong highlight
+            // isn't part of a BRDF and isn't scaled
+            let brdf_scale = match light.emits_radiance() {
+                true => FRAC_1_PI,
+                false => 1.0,
+            };
+
+            for _ in 0..samples {
+                let sample = Point2::new(rng.next_f32(), rng.next_f32());
+                let Some(light_sample) = light.sample(intersection.point, sample) else {
+                    continue;
+                };
+                let ray = Ray::new(
+                    (intersection.point + light_sample.direction * 1e-3).as_point(),
+                    light_sample.direction,
+                    Some(original_ray.medium_refraction),
+                )
+                .with_time(original_ray.time);
+                // Stop short of the light's own surface for emissive objects
+                let transmittance = self.shadow_transmittance(ray, light_sample.distance - 2e-3);
+                if transmittance.is_black() {
+                    continue;
+                }
+
+                let incoming = light_sample.radiance * transmittance * (1.0 / light_sample.pdf);
+                if light.diffuse() {
+                    let dot = light_sample.direction.dot(&intersection.normal);
+
+                    if dot > 0.0 {
+                        light_result = light_result
+                            + incoming
+                                * material.diffuse_color(intersection.texture_coord)
+                                * (dot * brdf_scale);
+                    }
+                }
+
+                // Specular
+                if specular != Specular::None && light.specular() {
+                    let dot = original_ray
+                        .direction
+                        .dot(&light_sample.direction.reflect(&intersection.normal));
+                    let exponent = material.specular_exponent;
+                    let highlight = match specular {
+                        Specular::NormalizedPhong => {
+                            (exponent + 2.0)
+                                * 0.5
+                                * light_sample.direction.dot(&intersection.normal).max(0.0)
+                                * brdf_scale
+                        }
+                        _ => 1.0,
+                    };
+
+                    if dot > 0.0 {
+                        light_result = light_result
+                            + incoming
+                                * material.specular_color(intersection.texture_coord)
+                                * (dot.powf(exponent) * highlight);
+                    }
+                }
+            }
+
+            result = result + light_result * (1.0 / samples as f32);
+        }
+
+        result
+    }
+
+    fn reflect(
+        &self,
+        intersection: &Intersection,
+        original_ray: Ray,
+        current_depth: u32,
+        rng: &mut Rng,
+    ) -> Color {
+        let new_direction = original_ray
+            .direction
+            .reflect(&intersection.normal)
+            .normalize();
+
+        let new_ray = Ray::new(
+            (intersection.point + new_direction * RAY_OFFSET).as_point(),
+            new_direction,
+            Some(original_ray.medium_refraction),
+        )
+        .with_time(original_ray.time);
+
+        let reflected_color = self.trace(new_ray, current_depth - 1, false, rng).total();
+
+        reflected_color
+            * intersection
+                .shape
+                .material()
+                .reflection_coefficient
+                .unwrap_or(0.0)
+    }
+
+    fn refract(
+        &self,
+        intersection: &Intersection,
+        original_ray: Ray,
+        current_depth: u32,
+        refraction_properties: &RefractionProperties,
+        rng: &mut Rng,
+    ) -> Color {
+        assert!(
+            intersection.shape.material().is_refractive(),
+            "Don't call refract for materials that aren't refractive"
+        );
+        let (c2, n, normal, cos_i, n2) = (
+            refraction_properties.c2,
+            refraction_properties.n,
+            refraction_properties.normal,
+            refraction_properties.cos_i,
+            refraction_properties.n2,
+        );
+
+        if c2 > 0.0 {
+            let direction =
+                (original_ray.direction * n + normal * (n * cos_i - c2.sqrt())).normalize();
+
+            let new_ray = Ray::new(
+                (intersection.point + direction * RAY_OFFSET).as_point(),
+                direction,
+                Some(n2),
+            )
+            .with_time(original_ray.time);
+
+            let refraction_color = self.trace(new_ray, current_depth - 1, false, rng).total();
+
+            return refraction_color * Self::transmittance(intersection);
+        }
+
+        Color::black()
+    }
+
+    /// The fraction of light that makes it `distance` along `ray`. Light
+    /// passes through refractive surfaces, ignoring the bend, and is absorbed
+    /// inside of them. Partially opaque surfaces let some of it through and
+    /// cut outs all of it.
+    fn shadow_transmittance(&self, ray: Ray, distance: f32) -> Color {
+        // Most shadow rays are either unobstructed or blocked by something solid
+        match self.scene.first_intersection(ray, false, distance) {
+            None => return Color::white(),
+            Some(hit) if Self::is_opaque(&hit) => return Color::black(),
+            Some(_) => {}
+        }
+
+        let mut transmittance = Color::white();
+        let mut ray = ray;
+        let mut remaining = distance;
+
+        for _ in 0..MAX_SHADOW_SURFACES {
+            let Some(hit) = self
+                .scene
+                .intersect(ray, false)
+                .filter(|hit| hit.t < remaining)
+            else {
+                return transmittance;
+            };
+            if Self::is_opaque(&hit) {
+                return Color::black();
+            }
+
+            let material = hit.shape.material();
+            if material.is_refractive() {
+                let refraction_properties = RefractionProperties::new(&hit, &ray);
+                if refraction_properties.total_internal_reflection() {
+                    return Color::black();
+                }
+                if material.illumination_model
+                    == IllumninationModel::DiffuseSpecularRefractedFresnel
+                {
+                    transmittance = transmittance * (1.0 - self.fresnel(&refraction_properties));
+                }
+                if hit.inside {
+                    transmittance = transmittance * Self::transmittance(&hit);
+                }
+            } else if !material.is_cut_out(hit.texture_coord) {
+                transmittance = transmittance * (1.0 - material.opacity(hit.texture_coord));
+            }
+            if transmittance.is_black() {
+                return transmittance;
+            }
+
+            remaining -= hit.t + RAY_OFFSET;
+            ray = Ray::new(
+                (hit.point + ray.direction * RAY_OFFSET).as_point(),
+                ray.direction,
+                Some(ray.medium_refraction),
+            )
+            .with_time(ray.time);
+        }
+
+        Color::black()
+    }
+
+    /// Whether the surface at `intersection` stops all light
+    fn is_opaque(intersection: &Intersection) -> bool {
+        let material = intersection.shape.material();
+
+        !material.is_refractive() && material.opacity(intersection.texture_coord) >= 1.0
+    }
+
+    /// Light that has travelled through a refractive object to `intersection`
+    /// is partially absorbed, more so for darker ambient colors
+    fn transmittance(intersection: &Intersection) -> Color {
+        let absorbance = intersection
+            .shape
+            .material()
+            .ambient_color(intersection.texture_coord)
+            * (0.15 * intersection.t);
+
+        absorbance.map(|channel| (-channel).exp())
+    }
+
+    fn fresnel(&self, refraction_properties: &RefractionProperties) -> f32 {
+        let (n1, n2, cos_i) = (
+            refraction_properties.n1,
+            refraction_properties.n2,
+            refraction_properties.cos_i,
+        );
+        let sin_t = n1 / n2 * (1.0 - cos_i * cos_i).max(0.0).sqrt();
+        let cos_t = (1.0 - sin_t * sin_t).max(0.0).sqrt();
+        let cos_i = cos_i.abs();
+
+        let rs = ((n2 * cos_i) - (n1 * cos_t)) / ((n2 * cos_i) + (n1 * cos_t));
+        let rp = ((n1 * cos_t) - (n2 * cos_i)) / ((n1 * cos_t) + (n2 * cos_i));
+
+        (rs * rs + rp * rp) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::camera::{Frame, Perspective, Shutter};
+    use crate::geometry::{Intersectable, Motion, Sphere};
+    use crate::material::MaterialTemplate;
+    use crate::math::{Matrix4, Point3, Vector3};
+    use crate::sampler::Uniform;
+
+    fn renderer_with_blocker(build: impl Fn(&mut Material)) -> Renderer {
+        let template = MaterialTemplate::new(
+            Color::black(),
+            Color::white(),
+            Color::black(),
+            0.0,
+            IllumninationModel::Diffuse,
+            None,
+            None,
+        );
+        let blocker: Box<dyn Intersectable> = Box::new(Sphere::new(
+            Point3::new(0.0, 5.0, 0.0),
+            1.0,
+            Rc::new(template.build_material(build)),
+        ));
+        let frame = Frame::new(
+            8,
+            4,
+            Point3::at_origin(),
+            Point3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+
+        Renderer::new(
+            Scene::new(vec![blocker], vec![], Color::black(), Color::black()),
+            Box::new(Perspective::new(frame, 1.0)),
+            RenderSettings {
+                sampler: Box::new(Uniform::new(0)),
+                ..RenderSettings::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_shadow_transmittance() {
+        let up = Ray::new(Point3::at_origin(), Vector3::new(0.0, 1.0, 0.0), None);
+        let transmittance = |build: &dyn Fn(&mut Material)| {
+            renderer_with_blocker(build).shadow_transmittance(up, 10.0)
+        };
+
+        assert_eq!(transmittance(&|_| {}), Color::black());
+        assert_eq!(
+            renderer_with_blocker(|_| {}).shadow_transmittance(up, 3.0),
+            Color::white(),
+            "Blockers past the light cast no shadow"
+        );
+        // Partially opaque surfaces on both sides of the sphere
+        assert_eq_within_bound!(
+            transmittance(&|material| material.dissolve = 0.75).r(),
+            0.0625,
+            1e-5
+        );
+        assert_eq!(
+            transmittance(&|material| material.dissolve = 0.0),
+            Color::white(),
+            "Cut outs cast no shadow"
+        );
+
+        // Glass absorbs the colors opposite of its ambient color
+        let glass = transmittance(&|material| {
+            material.illumination_model = IllumninationModel::DiffuseSpecularRefracted;
+            material.refraction_coefficient = Some(1.5);
+            material.ambient_color = Color::new(0.0, 1.0, 1.0);
+        });
+        assert_eq!(glass.r(), 1.0);
+        assert!(glass.g() > 0.0 && glass.g() < 1.0);
+    }
+
+    #[test]
+    fn test_default_settings_blur_motion() {
+        let template = MaterialTemplate::new(
+            Color::black(),
+            Color::white(),
+            Color::black(),
+            0.0,
+            IllumninationModel::Constant,
+            None,
+            None,
+        );
+        // Crosses the image from left to right while the shutter is open
+        let mut sphere = Sphere::new(
+            Point3::new(-2.0, 0.0, -5.0),
+            1.0,
+            Rc::new(template.build_material(|_| {})),
+        );
+        sphere.set_motion(Motion::new(vec![
+            (0.0, Matrix4::identity()),
+            (1.0, Matrix4::translate(4.0, 0.0, 0.0)),
+        ]));
+        let mut frame = Frame::new(
+            16,
+            16,
+            Point3::at_origin(),
+            Point3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        frame.shutter = Shutter {
+            open: 0.0,
+            close: 1.0,
+        };
+        let renderer = Renderer::new(
+            Scene::new(
+                vec![Box::new(sphere)],
+                vec![],
+                Color::black(),
+                Color::black(),
+            ),
+            Box::new(Perspective::new(frame, 1.0)),
+            RenderSettings::default(),
+        );
+
+        let framebuffer = renderer.render_framebuffer(1);
+        let lit: Vec<_> = (0..16)
+            .flat_map(|y| (0..16).map(move |x| (x, y)))
+            .filter(|(x, y)| framebuffer.pixel(*x, *y).r() > 0.0)
+            .map(|(x, _)| x)
+            .collect();
+        let (left, right) = (lit.iter().min().unwrap(), lit.iter().max().unwrap());
+
+        // Frozen at a single time the sphere is about six pixels wide, blurred
+        // it leaves a trail across its path
+        assert!(
+            *left < 4 && *right > 11,
+            "Only columns {}-{} are lit",
+            left,
+            right
+        );
+    }
+
+    #[test]
+    fn test_denoiser_features_are_not_output() {
+        let frame = Frame::new(
+            8,
+            4,
+            Point3::at_origin(),
+            Point3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        let renderer = Renderer::new(
+            Scene::new(vec![], vec![], Color::black(), Color::black()),
+            Box::new(Perspective::new(frame, 1.0)),
+            RenderSettings {
+                aovs: vec![Aov::Normal, Aov::ObjectId],
+                denoiser: Some(Denoiser::default()),
+                ..RenderSettings::default()
+            },
+        );
+
+        assert_eq!(renderer.aovs(), &[Aov::Normal, Aov::ObjectId]);
+        assert_eq!(renderer.aovs.len(), 4);
+    }
+
+    #[test]
+    fn test_running_variance() {
+        let mut variance = RunningVariance::default();
+        assert_eq!(variance.variance_of_mean(), f32::INFINITY);
+
+        for value in &[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            variance.add(*value);
+        }
+
+        // Sample variance of the values is 32 / 7
+        assert_eq_within_bound!(variance.mean, 5.0, 1e-5);
+        assert_eq_within_bound!(variance.variance_of_mean(), 32.0 / 7.0 / 8.0, 1e-5);
+    }
+}
@@ -0,0 +1,330 @@
+use std::f32::consts::PI;
+
+use super::{Lighting, RefractionProperties, Renderer, Specular, RAY_OFFSET};
+use crate::color::Color;
+use crate::intersection::Intersection;
+use crate::material::IllumninationModel;
+use crate::math::{Rng, Vector3};
+use crate::ray::Ray;
+
+/// Number of bounces before paths become candidates for russian roulette
+/// termination.
+const MIN_BOUNCES: u32 = 3;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Lobe {
+    Diffuse,
+    Glossy,
+    Mirror,
+    Transmission,
+}
+
+/// The result of sampling the material at a path vertex, `weight` is the BRDF
+/// times cosine divided by the probability of having sampled `direction`.
+struct Bounce {
+    direction: Vector3,
+    weight: Color,
+    medium_refraction: f32,
+    lobe: Lobe,
+}
+
+impl Lighting {
+    /// Add radiance that reached the camera after leaving the first surface
+    /// via `first_lobe`, `None` when it arrived directly.
+    fn add(&mut self, first_lobe: Option<Lobe>, radiance: Color) {
+        let component = match first_lobe {
+            None => &mut self.direct,
+            Some(Lobe::Diffuse) | Some(Lobe::Glossy) => &mut self.indirect,
+            Some(Lobe::Mirror) => &mut self.reflection,
+            Some(Lobe::Transmission) => &mut self.refraction,
+        };
+
+        *component = *component + radiance;
+    }
+}
+
+impl Renderer {
+    /// Trace a path starting with `camera_ray`, which intersects the scene at
+    /// `primary`
+    pub(super) fn trace_path(
+        &self,
+        camera_ray: Ray,
+        primary: Option<(usize, Intersection)>,
+        max_depth: u32,
+        rng: &mut Rng,
+    ) -> Lighting {
+        let mut lighting = Lighting::black();
+        let mut first_lobe = None;
+        let mut last_lobe = None;
+        let mut throughput = Color::white();
+        let mut ray = camera_ray;
+
+        for bounce in 0..max_depth {
+            let hit = match bounce {
+                0 => primary,
+                _ => self.scene.intersect_object(ray, false),
+            };
+            let Some((index, hit)) = hit else {
+                // The environment was already sampled as a light for diffuse and
+                // glossy bounces
+                let sampled = self.scene.environment.is_some()
+                    && matches!(last_lobe, Some(Lobe::Diffuse | Lobe::Glossy));
+                if !sampled {
+                    lighting.add(
+                        first_lobe,
+                        throughput * self.scene.background(&ray, bounce == 0),
+                    );
+                }
+                break;
+            };
+            let material = hit.shape.material();
+
+            if material.illumination_model == IllumninationModel::Constant {
+                lighting.add(
+                    first_lobe,
+                    throughput * material.diffuse_color(hit.texture_coord),
+                );
+                break;
+            }
+
+            // Emissive objects with lights were already sampled for diffuse and
+            // glossy bounces
+            if material.is_emissive() {
+                let sampled = self.scene.is_light(index)
+                    && matches!(last_lobe, Some(Lobe::Diffuse | Lobe::Glossy));
+                if !sampled {
+                    lighting.add(
+                        first_lobe,
+                        throughput * material.emission(hit.texture_coord),
+                    );
+                }
+            }
+
+            // Light that has travelled through a refractive object is partially absorbed
+            if hit.inside {
+                throughput = throughput * Self::transmittance(&hit);
+            }
+
+            // The same lobes as `sample_bounce`
+            let specular = if material.illumination_model != IllumninationModel::Diffuse
+                && material.specular_exponent > 0.0
+            {
+                Specular::NormalizedPhong
+            } else {
+                Specular::None
+            };
+            lighting.add(
+                first_lobe,
+                throughput * self.direct_lighting(&hit, ray, specular, rng),
+            );
+
+            let Some(sample) = self.sample_bounce(&hit, ray, rng) else {
+                break;
+            };
+            throughput = throughput * sample.weight;
+            if bounce == 0 {
+                first_lobe = Some(sample.lobe);
+            }
+            last_lobe = Some(sample.lobe);
+
+            if bounce >= MIN_BOUNCES {
+                let survival_probability = throughput.max_component().min(0.95);
+
+                if rng.next_f32() >= survival_probability {
+                    break;
+                }
+
+                throughput = throughput * (1.0 / survival_probability);
+            }
+
+            ray = Ray::new(
+                (hit.point + sample.direction * RAY_OFFSET).as_point(),
+                sample.direction,
+                Some(sample.medium_refraction),
+            )
+            .with_time(ray.time);
+        }
+
+        lighting
+    }
+
+    fn sample_bounce(
+        &self,
+        intersection: &Intersection,
+        ray: Ray,
+        rng: &mut Rng,
+    ) -> Option<Bounce> {
+        let material = intersection.shape.material();
+        let uv = intersection.texture_coord;
+        let normal = if intersection.normal.dot(&ray.direction) > 0.0 {
+            -intersection.normal
+        } else {
+            intersection.normal
+        };
+
+        let diffuse = material.diffuse_color(uv);
+        let glossy = if material.illumination_model != IllumninationModel::Diffuse
+            && material.specular_exponent > 0.0
+        {
+            material.specular_color(uv)
+        } else {
+            Color::black()
+        };
+
+        let reflection_coefficient = material.reflection_coefficient.unwrap_or(0.0);
+        let refraction_properties = if material.is_refractive() {
+            Some(RefractionProperties::new(intersection, &ray))
+        } else {
+            None
+        };
+
+        let (mirror, transmission) = match material.illumination_model {
+            IllumninationModel::Constant
+            | IllumninationModel::Diffuse
+            | IllumninationModel::DiffuseSpecular => (0.0, 0.0),
+            IllumninationModel::DiffuseSpecularReflective
+            | IllumninationModel::DiffuseSpecularReflectiveGlass
+            | IllumninationModel::DiffuseSpecularFresnel => (reflection_coefficient, 0.0),
+            IllumninationModel::DiffuseSpecularRefracted => match &refraction_properties {
+                Some(properties) if !properties.total_internal_reflection() => {
+                    (reflection_coefficient, 1.0)
+                }
+                _ => (reflection_coefficient, 0.0),
+            },
+            IllumninationModel::DiffuseSpecularRefractedFresnel => match &refraction_properties {
+                Some(properties) => {
+                    let kr = self.fresnel(properties);
+
+                    if properties.total_internal_reflection() {
+                        (reflection_coefficient * kr, 0.0)
+                    } else {
+                        (reflection_coefficient * kr, 1.0 - kr)
+                    }
+                }
+                None => (reflection_coefficient, 0.0),
+            },
+        };
+
+        // Pick one lobe with a probability proportional to its contribution
+        let lobe_weights = [diffuse.average(), glossy.average(), mirror, transmission];
+        let total_weight: f32 = lobe_weights.iter().sum();
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        let mut selection = rng.next_f32() * total_weight;
+        let mut lobe = 0;
+        while lobe < lobe_weights.len() - 1 && selection >= lobe_weights[lobe] {
+            selection -= lobe_weights[lobe];
+            lobe += 1;
+        }
+        let probability = lobe_weights[lobe] / total_weight;
+        if probability <= 0.0 {
+            return None;
+        }
+        let reflected = ray.direction.reflect(&normal).normalize();
+
+        let bounce = match lobe {
+            0 => Bounce {
+                // The cosine term and the pdf of cosine weighted sampling cancel out
+                direction: sample_cosine_hemisphere(normal, rng),
+                weight: diffuse * (1.0 / probability),
+                medium_refraction: ray.medium_refraction,
+                lobe: Lobe::Diffuse,
+            },
+            1 => {
+                let exponent = material.specular_exponent;
+                let direction = sample_phong_lobe(reflected, exponent, rng);
+                let cos_theta = direction.dot(&normal);
+
+                if cos_theta <= 0.0 {
+                    return None;
+                }
+
+                Bounce {
+                    direction,
+                    // Normalized Phong lobe, see Lafortune and Willems 1994
+                    weight: glossy
+                        * ((exponent + 2.0) / (exponent + 1.0) * cos_theta / probability),
+                    medium_refraction: ray.medium_refraction,
+                    lobe: Lobe::Glossy,
+                }
+            }
+            2 => Bounce {
+                direction: reflected,
+                weight: Color::white() * (mirror / probability),
+                medium_refraction: ray.medium_refraction,
+                lobe: Lobe::Mirror,
+            },
+            _ => {
+                let properties = refraction_properties
+                    .expect("Transmission is only sampled for refractive materials");
+                let direction = (ray.direction * properties.n
+                    + properties.normal * (properties.n * properties.cos_i - properties.c2.sqrt()))
+                .normalize();
+
+                Bounce {
+                    direction,
+                    weight: Color::white() * (transmission / probability),
+                    medium_refraction: properties.n2,
+                    lobe: Lobe::Transmission,
+                }
+            }
+        };
+
+        Some(bounce)
+    }
+}
+
+fn sample_cosine_hemisphere(normal: Vector3, rng: &mut Rng) -> Vector3 {
+    let (u1, u2) = (rng.next_f32(), rng.next_f32());
+    let radius = u1.sqrt();
+    let phi = 2.0 * PI * u2;
+    let (tangent, bitangent) = normal.orthonormal_basis();
+
+    (tangent * (radius * phi.cos())
+        + bitangent * (radius * phi.sin())
+        + normal * (1.0 - u1).max(0.0).sqrt())
+    .normalize()
+}
+
+fn sample_phong_lobe(axis: Vector3, exponent: f32, rng: &mut Rng) -> Vector3 {
+    let (u1, u2) = (rng.next_f32(), rng.next_f32());
+    let cos_alpha = u1.powf(1.0 / (exponent + 1.0));
+    let sin_alpha = (1.0 - cos_alpha * cos_alpha).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    let (tangent, bitangent) = axis.orthonormal_basis();
+
+    (tangent * (sin_alpha * phi.cos()) + bitangent * (sin_alpha * phi.sin()) + axis * cos_alpha)
+        .normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_cosine_hemisphere() {
+        let normal = Vector3::new(0.3, -0.8, 0.2).normalize();
+        let mut rng = Rng::new(1);
+
+        for _ in 0..1000 {
+            let direction = sample_cosine_hemisphere(normal, &mut rng);
+
+            assert_eq_within_bound!(direction.length(), 1.0, 1e-4);
+            assert!(direction.dot(&normal) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_sample_phong_lobe_concentrates_around_axis() {
+        let axis = Vector3::new(0.0, 1.0, 0.0);
+        let mut rng = Rng::new(2);
+
+        for _ in 0..1000 {
+            let direction = sample_phong_lobe(axis, 1000.0, &mut rng);
+
+            assert!(direction.dot(&axis) > 0.9);
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::{Renderer, SampleBudget};
+use crate::checkpoint::Checkpoint;
+use crate::config;
+use crate::framebuffer::Framebuffer;
+use crate::scheduler::CancellationToken;
+
+/// Settings for progressive rendering. Passes are rendered until one of the
+/// stopping criteria is met, if none are given the render stops after the
+/// number of samples the configured super sampling would take.
+#[derive(Debug, Copy, Clone)]
+pub struct Progressive {
+    pub samples_per_pass: u32,
+    /// Stop once every pixel has this many samples
+    pub target_samples: Option<u32>,
+    /// Stop after the first pass that ends past this budget
+    pub time_limit: Option<Duration>,
+    /// Stop once the estimated noise, the mean absolute error of the tone
+    /// mapped luminance, falls below this value
+    pub noise_threshold: Option<f32>,
+    /// Report an intermediate image every N passes
+    pub update_every_passes: Option<u32>,
+    /// Report an intermediate image when this much time has passed since the
+    /// previous one
+    pub update_every: Option<Duration>,
+    /// Ask for a checkpoint when this much time has passed since the previous
+    /// one, and once more when the render is done
+    pub checkpoint_every: Option<Duration>,
+}
+
+impl Default for Progressive {
+    fn default() -> Self {
+        Self {
+            samples_per_pass: 1,
+            target_samples: None,
+            time_limit: None,
+            noise_threshold: None,
+            update_every_passes: None,
+            update_every: None,
+            checkpoint_every: None,
+        }
+    }
+}
+
+/// Everything needed to continue a progressive render.
+#[derive(Debug, Clone)]
+pub struct ProgressiveState {
+    pub passes: u32,
+    /// Samples taken per pixel so far, also the index of the next sample
+    pub samples_per_pixel: u32,
+    /// Render time, summed over all sessions of a resumed render
+    pub elapsed: Duration,
+    pub accumulation: Framebuffer,
+    /// Every other pass is also accumulated separately when a noise threshold
+    /// is used, the difference to the full estimate tells how noisy the
+    /// latter still is.
+    pub even_passes: Option<Framebuffer>,
+}
+
+/// Passed to the update callback after a pass when output is due.
+#[derive(Debug, Copy, Clone)]
+pub struct ProgressiveUpdate {
+    /// The current noise estimate, only available when a noise threshold is
+    /// used and at least two passes are done
+    pub noise: Option<f32>,
+    pub image_due: bool,
+    pub checkpoint_due: bool,
+    /// Whether this is the last pass
+    pub done: bool,
+}
+
+impl Renderer {
+    /// Render the scene progressively. Each pass adds `samples_per_pass`
+    /// samples to every pixel of a float accumulation buffer, starting from
+    /// `resume` if given. `on_update` is called whenever an intermediate image
+    /// or a checkpoint is due and once more when the render is done.
+    ///
+    /// When `cancellation` is triggered the unfinished pass is discarded and
+    /// the state after the last complete pass is returned.
+    pub fn render_progressive<F>(
+        &self,
+        max_depth: u32,
+        settings: &Progressive,
+        resume: Option<ProgressiveState>,
+        cancellation: &CancellationToken,
+        mut on_update: F,
+    ) -> ProgressiveState
+    where
+        F: FnMut(&ProgressiveUpdate, &ProgressiveState),
+    {
+        let (width, height) = (self.camera.width(), self.camera.height());
+        let samples_per_pass = settings.samples_per_pass.max(1);
+        let target_samples = match settings {
+            Progressive {
+                target_samples: None,
+                time_limit: None,
+                noise_threshold: None,
+                ..
+            } => Some(self.super_sampling.max_samples_per_pixel()),
+            _ => settings.target_samples,
+        };
+        let mut state = resume.unwrap_or_else(|| ProgressiveState {
+            passes: 0,
+            samples_per_pixel: 0,
+            elapsed: Duration::ZERO,
+            accumulation: self.new_framebuffer(0, 0, width, height),
+            even_passes: settings
+                .noise_threshold
+                .map(|_| Framebuffer::new(width, height, 0)),
+        });
+        let is_done = |state: &ProgressiveState, noise: Option<f32>| {
+            target_samples.is_some_and(|target| state.samples_per_pixel >= target)
+                || settings
+                    .time_limit
+                    .is_some_and(|limit| state.elapsed >= limit)
+                || settings
+                    .noise_threshold
+                    .zip(noise)
+                    .is_some_and(|(threshold, noise)| noise <= threshold)
+        };
+
+        let start = Instant::now();
+        let resumed_elapsed = state.elapsed;
+        let (mut last_image, mut last_checkpoint) = (start, start);
+        let mut done = state.passes > 0 && is_done(&state, self.noise(&state));
+
+        while !done {
+            let budget = SampleBudget::fixed(state.samples_per_pixel, samples_per_pass);
+            let pass = self.render_pass(max_depth, &budget, cancellation, |_, _| {});
+            if cancellation.is_cancelled() {
+                // A partial pass would leave some pixels with fewer samples
+                break;
+            }
+
+            state.accumulation.merge(&pass);
+            if let (Some(even), true) = (state.even_passes.as_mut(), state.passes.is_multiple_of(2))
+            {
+                even.merge(&pass);
+            }
+            state.passes += 1;
+            state.samples_per_pixel += samples_per_pass;
+
+            let now = Instant::now();
+            state.elapsed = resumed_elapsed + (now - start);
+            let noise = self.noise(&state);
+            done = is_done(&state, noise);
+
+            let update = ProgressiveUpdate {
+                noise,
+                image_due: done
+                    || settings
+                        .update_every_passes
+                        .is_some_and(|every| state.passes.is_multiple_of(every.max(1)))
+                    || settings
+                        .update_every
+                        .is_some_and(|every| now - last_image >= every),
+                checkpoint_due: settings
+                    .checkpoint_every
+                    .is_some_and(|every| done || now - last_checkpoint >= every),
+                done,
+            };
+
+            if update.image_due {
+                last_image = now;
+            }
+            if update.checkpoint_due {
+                last_checkpoint = now;
+            }
+            if update.image_due || update.checkpoint_due {
+                on_update(&update, &state);
+            }
+        }
+
+        state
+    }
+
+    /// Load a checkpoint written while rendering with this renderer's camera
+    /// and AOVs
+    pub fn load_checkpoint(&self, path: &Path) -> io::Result<Checkpoint> {
+        Checkpoint::load(
+            path,
+            self.camera.width(),
+            self.camera.height(),
+            self.aovs.len(),
+        )
+    }
+
+    fn noise(&self, state: &ProgressiveState) -> Option<f32> {
+        match &state.even_passes {
+            Some(even) if state.passes >= 2 => Some(self.estimate_noise(&state.accumulation, even)),
+            _ => None,
+        }
+    }
+
+    fn estimate_noise(&self, accumulation: &Framebuffer, even_passes: &Framebuffer) -> f32 {
+        let (width, height) = (accumulation.width(), accumulation.height());
+        let mut total_error = 0.0;
+
+        for y in 0..height {
+            for x in 0..width {
+                let full = self.tone_mapping.apply(accumulation.pixel(x, y));
+                let half = self.tone_mapping.apply(even_passes.pixel(x, y));
+
+                total_error += (full.luminance() - half.luminance()).abs();
+            }
+        }
+
+        total_error / (width * height).max(1) as f32
+    }
+}
+
+impl From<&config::Progressive> for Progressive {
+    fn from(config: &config::Progressive) -> Self {
+        let defaults = Self::default();
+
+        Self {
+            samples_per_pass: config.samples_per_pass.unwrap_or(defaults.samples_per_pass),
+            target_samples: config.target_samples,
+            time_limit: config.time_limit_seconds.map(Duration::from_secs_f32),
+            noise_threshold: config.noise_threshold,
+            update_every_passes: config.update_every_passes,
+            update_every: config.update_every_seconds.map(Duration::from_secs_f32),
+            checkpoint_every: defaults.checkpoint_every,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::{Frame, Perspective};
+    use crate::color::Color;
+    use crate::filter::Filter;
+    use crate::math::{Point3, Vector3};
+    use crate::renderer::RenderSettings;
+    use crate::sampler::Uniform;
+    use crate::scene::Scene;
+
+    fn empty_renderer() -> Renderer {
+        let frame = Frame::new(
+            8,
+            4,
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+
+        Renderer::new(
+            Scene::new(vec![], vec![], Color::black(), Color::white()),
+            Box::new(Perspective::new(frame, 1.0)),
+            RenderSettings {
+                sampler: Box::new(Uniform::new(0)),
+                ..RenderSettings::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_passes_accumulate_until_target() {
+        let renderer = empty_renderer();
+        let settings = Progressive {
+            samples_per_pass: 2,
+            target_samples: Some(6),
+            update_every_passes: Some(2),
+            ..Progressive::default()
+        };
+        let mut updates = vec![];
+
+        let state = renderer.render_progressive(
+            1,
+            &settings,
+            None,
+            &CancellationToken::new(),
+            |_, state| updates.push(state.samples_per_pixel),
+        );
+
+        assert_eq!(updates, vec![4, 6]);
+        assert_eq!(state.passes, 3);
+        assert_eq!(state.accumulation.sample_count(3, 2), 6);
+        assert_eq!(state.accumulation.pixel(3, 2), Color::white());
+    }
+
+    #[test]
+    fn test_resuming_matches_uninterrupted_render() {
+        let mut renderer = empty_renderer();
+        renderer.filter = Filter::Gaussian {
+            radius: 1.5,
+            alpha: 2.0,
+        };
+        let render = |target_samples, resume| {
+            let settings = Progressive {
+                target_samples: Some(target_samples),
+                ..Progressive::default()
+            };
+
+            renderer.render_progressive(1, &settings, resume, &CancellationToken::new(), |_, _| {})
+        };
+        let serialize = |state: &ProgressiveState| {
+            let mut bytes = vec![];
+            state.accumulation.write_to(&mut bytes).unwrap();
+
+            bytes
+        };
+
+        let uninterrupted = render(4, None);
+        let resumed = render(4, Some(render(2, None)));
+
+        assert_eq!(resumed.passes, 4);
+        assert_eq!(serialize(&resumed), serialize(&uninterrupted));
+    }
+}
@@ -0,0 +1,221 @@
+use super::{rotate, Sampler};
+use crate::math::{hash_all, Point2, Rng};
+
+/// Side length of the tileable blue noise masks
+const MASK_SIZE: usize = 64;
+/// Standard deviation of the gaussian energy filter used by void and cluster
+const SIGMA: f32 = 1.9;
+/// Beyond this distance the gaussian energy contribution is negligible
+const KERNEL_RADIUS: i32 = 6;
+/// Fraction of the mask that is populated with the initial binary pattern
+const INITIAL_DENSITY: f32 = 0.1;
+
+// The plastic constant based R2 sequence, see
+// http://extremelearning.com.au/unreasonable-effectiveness-of-quasirandom-sequences/
+const R2_ALPHA_X: f64 = 0.754_877_666_246_692_8;
+const R2_ALPHA_Y: f64 = 0.569_840_290_998_053_3;
+
+/// Low discrepancy R2 samples with a per pixel Cranley-Patterson rotation
+/// read from a pair of blue noise masks. The error of neighbouring pixels is
+/// negatively correlated which pushes the remaining noise to high frequencies
+/// where it is much less objectionable.
+pub struct BlueNoise {
+    masks: [Vec<f32>; 2],
+}
+
+impl BlueNoise {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            masks: [
+                void_and_cluster(hash_all(&[seed, 0])),
+                void_and_cluster(hash_all(&[seed, 1])),
+            ],
+        }
+    }
+
+    fn lookup(&self, mask: usize, x: u32, y: u32) -> f32 {
+        let x = x as usize % MASK_SIZE;
+        let y = y as usize % MASK_SIZE;
+
+        self.masks[mask][y * MASK_SIZE + x]
+    }
+}
+
+impl Sampler for BlueNoise {
+    fn sample_2d(&self, x: u32, y: u32, index: u32, dimension: u32) -> Point2 {
+        let sample = Point2::new(
+            (0.5 + R2_ALPHA_X * f64::from(index)).fract() as f32,
+            (0.5 + R2_ALPHA_Y * f64::from(index)).fract() as f32,
+        );
+
+        // Each dimension reads the masks at a different offset so that
+        // dimensions aren't correlated.
+        let (offset_x, offset_y) = (dimension * 23, dimension * 41);
+        let offset = Point2::new(
+            self.lookup(0, x + offset_x, y + offset_y),
+            self.lookup(1, x + offset_x, y + offset_y),
+        );
+
+        rotate(sample, offset)
+    }
+}
+
+/// Generate a tileable blue noise threshold mask with Ulichney's void and
+/// cluster method. The result contains each of the values `(rank + 0.5) / N`
+/// exactly once.
+fn void_and_cluster(seed: u64) -> Vec<f32> {
+    let size = MASK_SIZE * MASK_SIZE;
+    let kernel = gaussian_kernel();
+    let mut rng = Rng::new(seed);
+    let mut pattern = vec![false; size];
+    let mut energy = vec![0.0; size];
+
+    let initial_count = (size as f32 * INITIAL_DENSITY) as usize;
+    let mut placed = 0;
+    while placed < initial_count {
+        let index = (rng.next_u32() as usize) % size;
+
+        if !pattern[index] {
+            pattern[index] = true;
+            update_energy(&mut energy, &kernel, index, 1.0);
+            placed += 1;
+        }
+    }
+
+    // Spread the initial pattern out by moving points from the tightest
+    // clusters to the largest voids until that no longer changes anything.
+    loop {
+        let cluster = tightest_cluster(&pattern, &energy);
+        pattern[cluster] = false;
+        update_energy(&mut energy, &kernel, cluster, -1.0);
+
+        let void = largest_void(&pattern, &energy);
+        pattern[void] = true;
+        update_energy(&mut energy, &kernel, void, 1.0);
+
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; size];
+
+    // Phase 1: rank the points of the initial pattern by removing them one
+    // cluster at a time.
+    {
+        let mut pattern = pattern.clone();
+        let mut energy = energy.clone();
+
+        for rank in (0..initial_count).rev() {
+            let cluster = tightest_cluster(&pattern, &energy);
+            pattern[cluster] = false;
+            update_energy(&mut energy, &kernel, cluster, -1.0);
+            ranks[cluster] = rank;
+        }
+    }
+
+    // Phase 2: fill the remaining mask by repeatedly inserting into the
+    // largest void.
+    for rank in initial_count..size {
+        let void = largest_void(&pattern, &energy);
+        pattern[void] = true;
+        update_energy(&mut energy, &kernel, void, 1.0);
+        ranks[void] = rank;
+    }
+
+    ranks
+        .into_iter()
+        .map(|rank| (rank as f32 + 0.5) / size as f32)
+        .collect()
+}
+
+fn gaussian_kernel() -> Vec<f32> {
+    let width = (2 * KERNEL_RADIUS + 1) as usize;
+    let mut kernel = Vec::with_capacity(width * width);
+
+    for dy in -KERNEL_RADIUS..=KERNEL_RADIUS {
+        for dx in -KERNEL_RADIUS..=KERNEL_RADIUS {
+            let distance_squared = (dx * dx + dy * dy) as f32;
+            kernel.push((-distance_squared / (2.0 * SIGMA * SIGMA)).exp());
+        }
+    }
+
+    kernel
+}
+
+fn update_energy(energy: &mut [f32], kernel: &[f32], index: usize, sign: f32) {
+    let size = MASK_SIZE as i32;
+    let width = 2 * KERNEL_RADIUS + 1;
+    let (x, y) = ((index % MASK_SIZE) as i32, (index / MASK_SIZE) as i32);
+
+    for dy in -KERNEL_RADIUS..=KERNEL_RADIUS {
+        for dx in -KERNEL_RADIUS..=KERNEL_RADIUS {
+            let wrapped_x = (x + dx).rem_euclid(size);
+            let wrapped_y = (y + dy).rem_euclid(size);
+            let weight = kernel[((dy + KERNEL_RADIUS) * width + dx + KERNEL_RADIUS) as usize];
+
+            energy[(wrapped_y * size + wrapped_x) as usize] += sign * weight;
+        }
+    }
+}
+
+fn tightest_cluster(pattern: &[bool], energy: &[f32]) -> usize {
+    let mut best = (0, f32::NEG_INFINITY);
+
+    for (index, value) in energy.iter().enumerate() {
+        if pattern[index] && *value > best.1 {
+            best = (index, *value);
+        }
+    }
+
+    best.0
+}
+
+fn largest_void(pattern: &[bool], energy: &[f32]) -> usize {
+    let mut best = (0, f32::INFINITY);
+
+    for (index, value) in energy.iter().enumerate() {
+        if !pattern[index] && *value < best.1 {
+            best = (index, *value);
+        }
+    }
+
+    best.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_contains_every_rank_once() {
+        let mask = void_and_cluster(3);
+        let size = MASK_SIZE * MASK_SIZE;
+        let mut seen = vec![false; size];
+
+        for value in mask {
+            let rank = (value * size as f32) as usize;
+
+            assert!(!seen[rank]);
+            seen[rank] = true;
+        }
+    }
+
+    #[test]
+    fn test_mask_has_little_low_frequency_energy() {
+        // Neighbouring values in blue noise are dissimilar, the average
+        // difference between neighbours is clearly above that of white noise
+        // (1/3 for uniformly distributed values).
+        let mask = void_and_cluster(5);
+        let mut total = 0.0;
+
+        for y in 0..MASK_SIZE {
+            for x in 0..MASK_SIZE {
+                let right = mask[y * MASK_SIZE + (x + 1) % MASK_SIZE];
+                total += (mask[y * MASK_SIZE + x] - right).abs();
+            }
+        }
+
+        assert!(total / (MASK_SIZE * MASK_SIZE) as f32 > 0.36);
+    }
+}
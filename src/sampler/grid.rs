@@ -0,0 +1,60 @@
+use super::{below_one, Sampler};
+use crate::math::{hash_all, Point2, Rng};
+
+/// Places samples in the center of the cells of a regular grid. Cheap but
+/// prone to aliasing on high frequency content.
+///
+/// Once every cell has a sample, like in later passes of progressive renders,
+/// further samples are jittered within their cells so that they keep adding
+/// information.
+///
+/// Only the position within the pixel sits on the grid. Other dimensions, like
+/// the lens and time, are jittered within their cells and visit the cells in an
+/// order rotated per pixel and dimension, so that they aren't correlated with
+/// the position or each other and vary between pixels even with a single
+/// sample.
+#[derive(Debug)]
+pub struct Grid {
+    resolution: u32,
+}
+
+impl Grid {
+    pub fn new(samples_per_pixel: u32) -> Self {
+        Self {
+            resolution: (samples_per_pixel as f32).sqrt().ceil().max(1.0) as u32,
+        }
+    }
+}
+
+impl Sampler for Grid {
+    fn sample_2d(&self, x: u32, y: u32, index: u32, dimension: u32) -> Point2 {
+        let num_cells = self.resolution * self.resolution;
+        let offset = match dimension {
+            0 => 0,
+            _ => {
+                (hash_all(&[u64::from(x), u64::from(y), u64::from(dimension)])
+                    % u64::from(num_cells)) as u32
+            }
+        };
+        let cell = (index % num_cells + offset) % num_cells;
+        let jitter = match (index / num_cells, dimension) {
+            (0, 0) => Point2::new(0.5, 0.5),
+            _ => {
+                let mut rng = Rng::new(hash_all(&[
+                    u64::from(x),
+                    u64::from(y),
+                    u64::from(index),
+                    u64::from(dimension),
+                ]));
+
+                Point2::new(rng.next_f32(), rng.next_f32())
+            }
+        };
+        let resolution = self.resolution as f32;
+
+        Point2::new(
+            below_one(((cell % self.resolution) as f32 + jitter.x) / resolution),
+            below_one(((cell / self.resolution) as f32 + jitter.y) / resolution),
+        )
+    }
+}
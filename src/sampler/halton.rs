@@ -0,0 +1,74 @@
+use super::{rotate, to_unit_float, Sampler};
+use crate::math::{hash_all, Point2};
+
+const PRIMES: [u32; 16] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
+
+/// The Halton low discrepancy sequence. Dimension `n` uses the radical
+/// inverses in the `2n`-th and `2n + 1`-th prime bases. Each pixel gets its own
+/// random Cranley-Patterson rotation to avoid structured artifacts between
+/// pixels.
+#[derive(Debug)]
+pub struct Halton {
+    seed: u64,
+}
+
+impl Halton {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl Sampler for Halton {
+    fn sample_2d(&self, x: u32, y: u32, index: u32, dimension: u32) -> Point2 {
+        let base_index = (dimension as usize * 2) % PRIMES.len();
+        let sample = Point2::new(
+            radical_inverse(index, PRIMES[base_index]),
+            radical_inverse(index, PRIMES[base_index + 1]),
+        );
+
+        let offset = hash_all(&[self.seed, u64::from(x), u64::from(y), u64::from(dimension)]);
+
+        rotate(
+            sample,
+            Point2::new(
+                to_unit_float(offset as u32),
+                to_unit_float((offset >> 32) as u32),
+            ),
+        )
+    }
+}
+
+/// Mirror the digits of `index` in `base` around the decimal point
+fn radical_inverse(mut index: u32, base: u32) -> f32 {
+    let inverse_base = 1.0 / f64::from(base);
+    let mut result = 0.0;
+    let mut factor = inverse_base;
+
+    while index > 0 {
+        result += f64::from(index % base) * factor;
+        index /= base;
+        factor *= inverse_base;
+    }
+
+    result as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::radical_inverse;
+
+    #[test]
+    fn test_radical_inverse() {
+        let base_2: Vec<f32> = (0..5).map(|i| radical_inverse(i, 2)).collect();
+        let base_3: Vec<f32> = (0..5).map(|i| radical_inverse(i, 3)).collect();
+
+        assert_eq!(base_2, vec![0.0, 0.5, 0.25, 0.75, 0.125]);
+        for (value, expected) in
+            base_3
+                .iter()
+                .zip(&[0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0, 4.0 / 9.0])
+        {
+            assert_eq_within_bound!(*value, *expected, 1e-6);
+        }
+    }
+}
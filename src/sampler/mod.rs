@@ -0,0 +1,177 @@
+use crate::math::Point2;
+
+mod blue_noise;
+mod grid;
+mod halton;
+mod sobol;
+mod stratified;
+mod uniform;
+
+pub use self::blue_noise::BlueNoise;
+pub use self::grid::Grid;
+pub use self::halton::Halton;
+pub use self::sobol::Sobol;
+pub use self::stratified::Stratified;
+pub use self::uniform::Uniform;
+
+/// Generates the sample positions used to integrate over pixels and any other
+/// domains (lens, time, etc) that need well distributed samples.
+///
+/// Samplers are stateless, a sample is fully determined by its pixel, index
+/// and dimension. This makes them trivially parallel and guarantees the same
+/// image for the same configuration.
+pub trait Sampler: Send + Sync {
+    /// The `index`-th sample for the pixel at (`x`, `y`) in [0, 1)². Each
+    /// `dimension` is an independent 2D domain, dimension 0 is the position
+    /// within the pixel.
+    fn sample_2d(&self, x: u32, y: u32, index: u32, dimension: u32) -> Point2;
+}
+
+/// The largest `f32` smaller than 1.0
+const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+/// Guard against rounding pushing a sample onto the open end of [0, 1)
+fn below_one(value: f32) -> f32 {
+    value.min(ONE_MINUS_EPSILON)
+}
+
+/// Map the full range of a `u32` onto [0, 1)
+fn to_unit_float(value: u32) -> f32 {
+    // Only the upper 24 bits fit in the mantissa of a f32
+    (value >> 8) as f32 * (1.0 / (1 << 24) as f32)
+}
+
+/// Toroidal shift of a sample, also known as a Cranley-Patterson rotation.
+fn rotate(sample: Point2, offset: Point2) -> Point2 {
+    Point2::new(
+        below_one((sample.x + offset.x).fract()),
+        below_one((sample.y + offset.y).fract()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: u32 = 16;
+
+    fn all_samplers() -> Vec<(&'static str, Box<dyn Sampler>)> {
+        vec![
+            ("grid", Box::new(Grid::new(SAMPLES))),
+            ("uniform", Box::new(Uniform::new(7))),
+            ("stratified", Box::new(Stratified::new(7, SAMPLES))),
+            ("halton", Box::new(Halton::new(7))),
+            ("sobol", Box::new(Sobol::new(7))),
+            ("blue noise", Box::new(BlueNoise::new(7))),
+        ]
+    }
+
+    #[test]
+    fn test_samples_are_in_unit_square() {
+        for (name, sampler) in all_samplers() {
+            for index in 0..64 {
+                for dimension in 0..4 {
+                    let sample = sampler.sample_2d(13, 21, index, dimension);
+
+                    assert!(
+                        (0.0..1.0).contains(&sample.x) && (0.0..1.0).contains(&sample.y),
+                        "{} produced {:?}",
+                        name,
+                        sample
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_samples_are_deterministic() {
+        for ((name, a), (_, b)) in all_samplers().into_iter().zip(all_samplers()) {
+            for index in 0..SAMPLES {
+                let first = a.sample_2d(3, 4, index, 1);
+                let second = b.sample_2d(3, 4, index, 1);
+
+                assert!(
+                    first.x == second.x && first.y == second.y,
+                    "{} isn't deterministic",
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_samples_cover_all_strata() {
+        // All samplers except uniform random place exactly one of 16 samples
+        // in each cell of a 4x4 grid.
+        for (name, sampler) in all_samplers().into_iter().filter(|(n, _)| *n != "uniform") {
+            let mut strata = [0; SAMPLES as usize];
+
+            for index in 0..SAMPLES {
+                let sample = sampler.sample_2d(5, 9, index, 0);
+                let cell = (sample.x * 4.0) as usize + (sample.y * 4.0) as usize * 4;
+                strata[cell] += 1;
+            }
+
+            if name == "halton" || name == "blue noise" {
+                // Rotated low discrepancy points aren't perfectly stratified,
+                // but no cell should be left far behind.
+                assert!(strata.iter().all(|count| *count <= 3), "{}", name);
+            } else {
+                assert!(strata.iter().all(|count| *count == 1), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_later_passes_take_new_samples() {
+        // Progressive renders keep asking for samples past the number the
+        // sampler was set up for
+        let mut samplers = all_samplers();
+        samplers.push(("single cell grid", Box::new(Grid::new(1))));
+
+        for (name, sampler) in samplers {
+            let first_pass: Vec<_> = (0..SAMPLES)
+                .map(|index| sampler.sample_2d(2, 7, index, 0))
+                .collect();
+
+            for index in SAMPLES..SAMPLES * 2 {
+                let sample = sampler.sample_2d(2, 7, index, 0);
+
+                assert!(
+                    first_pass
+                        .iter()
+                        .all(|other| other.x != sample.x || other.y != sample.y),
+                    "{} repeats sample {}",
+                    name,
+                    index
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_pixels_are_decorrelated() {
+        for (name, sampler) in all_samplers().into_iter().filter(|(n, _)| *n != "grid") {
+            let a = sampler.sample_2d(0, 0, 1, 0);
+            let b = sampler.sample_2d(1, 0, 1, 0);
+
+            assert!(a.x != b.x || a.y != b.y, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_grid_varies_other_dimensions() {
+        // Without super sampling every pixel takes its one sample in the
+        // center, the lens and time still have to vary
+        let grid = Grid::new(1);
+        let (a, b) = (grid.sample_2d(0, 0, 0, 0), grid.sample_2d(1, 0, 0, 0));
+        assert!(a.x == b.x && a.y == b.y);
+
+        let (a, b) = (grid.sample_2d(0, 0, 0, 2), grid.sample_2d(1, 0, 0, 2));
+        assert!(a.x != b.x || a.y != b.y);
+
+        let (lens, time) = (grid.sample_2d(0, 0, 0, 1), grid.sample_2d(0, 0, 0, 2));
+        assert!(lens.x != time.x || lens.y != time.y);
+    }
+}
@@ -0,0 +1,102 @@
+use super::{to_unit_float, Sampler};
+use crate::math::{hash_all, Point2};
+
+/// The first two dimensions of the Sobol sequence, which form a (0, 2)
+/// sequence in base 2. Every dimension and pixel gets its own Owen scrambling
+/// and index shuffling, see Burley 2020, "Practical Hash-based Owen
+/// Scrambling".
+#[derive(Debug)]
+pub struct Sobol {
+    seed: u64,
+}
+
+impl Sobol {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl Sampler for Sobol {
+    fn sample_2d(&self, x: u32, y: u32, index: u32, dimension: u32) -> Point2 {
+        let seed = hash_all(&[self.seed, u64::from(x), u64::from(y), u64::from(dimension)]);
+        let shuffled_index = nested_uniform_scramble(index, seed as u32);
+
+        Point2::new(
+            to_unit_float(nested_uniform_scramble(
+                shuffled_index.reverse_bits(),
+                (seed >> 32) as u32,
+            )),
+            to_unit_float(nested_uniform_scramble(
+                sobol_second_dimension(shuffled_index),
+                (seed >> 16) as u32 ^ 0x9e37_79b9,
+            )),
+        )
+    }
+}
+
+/// The second Sobol dimension, generated by the primitive polynomial `x + 1`.
+fn sobol_second_dimension(mut index: u32) -> u32 {
+    let mut direction = 1 << 31;
+    let mut result = 0;
+
+    while index != 0 {
+        if index & 1 == 1 {
+            result ^= direction;
+        }
+
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+
+    result
+}
+
+/// Laine and Karras' hash which only lets bits affect more significant bits
+fn laine_karras_permutation(mut value: u32, seed: u32) -> u32 {
+    value = value.wrapping_add(seed);
+    value ^= value.wrapping_mul(0x6c50_b47c);
+    value ^= value.wrapping_mul(0xb82f_1e52);
+    value ^= value.wrapping_mul(0xc7af_e638);
+    value ^= value.wrapping_mul(0x8d22_f6e6);
+
+    value
+}
+
+fn nested_uniform_scramble(value: u32, seed: u32) -> u32 {
+    laine_karras_permutation(value.reverse_bits(), seed).reverse_bits()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unscrambled_sequence() {
+        let points: Vec<(f32, f32)> = (0..4)
+            .map(|i: u32| {
+                (
+                    to_unit_float(i.reverse_bits()),
+                    to_unit_float(sobol_second_dimension(i)),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            points,
+            vec![(0.0, 0.0), (0.5, 0.5), (0.25, 0.75), (0.75, 0.25)]
+        );
+    }
+
+    #[test]
+    fn test_scrambling_preserves_elementary_intervals() {
+        // Scrambling is a permutation of the elementary intervals, the first 4
+        // bits of 16 scrambled values are all unique.
+        let mut seen = [false; 16];
+
+        for i in 0..16_u32 {
+            let value = nested_uniform_scramble(i.reverse_bits(), 1234) >> 28;
+            assert!(!seen[value as usize]);
+            seen[value as usize] = true;
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use super::{below_one, Sampler};
+use crate::math::{hash_all, Point2, Rng};
+
+/// Jittered sampling, the pixel is split into a grid of strata and each
+/// sample is placed randomly within its own stratum.
+#[derive(Debug)]
+pub struct Stratified {
+    seed: u64,
+    resolution: u32,
+}
+
+impl Stratified {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> Self {
+        Self {
+            seed,
+            resolution: (samples_per_pixel as f32).sqrt().ceil().max(1.0) as u32,
+        }
+    }
+}
+
+impl Sampler for Stratified {
+    fn sample_2d(&self, x: u32, y: u32, index: u32, dimension: u32) -> Point2 {
+        let num_strata = self.resolution * self.resolution;
+        let pixel_seed = hash_all(&[self.seed, u64::from(x), u64::from(y), u64::from(dimension)]);
+        // Rotate the stratum order per pixel and dimension so that dimensions
+        // aren't correlated with each other.
+        let offset = (pixel_seed % u64::from(num_strata)) as u32;
+        let stratum = (index + offset) % num_strata;
+
+        let mut rng = Rng::new(hash_all(&[pixel_seed, u64::from(index)]));
+        let resolution = self.resolution as f32;
+
+        Point2::new(
+            below_one(((stratum % self.resolution) as f32 + rng.next_f32()) / resolution),
+            below_one(((stratum / self.resolution) as f32 + rng.next_f32()) / resolution),
+        )
+    }
+}
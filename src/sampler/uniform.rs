@@ -0,0 +1,28 @@
+use super::Sampler;
+use crate::math::{hash_all, Point2, Rng};
+
+/// Independent uniformly distributed random samples.
+#[derive(Debug)]
+pub struct Uniform {
+    seed: u64,
+}
+
+impl Uniform {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl Sampler for Uniform {
+    fn sample_2d(&self, x: u32, y: u32, index: u32, dimension: u32) -> Point2 {
+        let mut rng = Rng::new(hash_all(&[
+            self.seed,
+            u64::from(x),
+            u64::from(y),
+            u64::from(index),
+            u64::from(dimension),
+        ]));
+
+        Point2::new(rng.next_f32(), rng.next_f32())
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
+use std::ops::Range;
 use std::path::Path;
 use std::rc::Rc;
 
@@ -10,14 +11,24 @@ use crate::color::Color;
 use crate::config;
 use crate::config::Object;
 use crate::geometry::{ExtentVolume, Intersectable, Octree, Transformable};
-use crate::geometry::{Plane, Sphere};
+use crate::geometry::{Motion, Plane, Sphere, AABB};
 use crate::intersection::Intersection;
 use crate::light;
 use crate::material::Material;
-use crate::math::{Point3, Vector3};
+use crate::math::{Matrix4, Point3, Vector3};
 use crate::mesh_loader::MeshLoader;
 use crate::ray::Ray;
 
+/// Matrices sampled per pair of motion keys
+const MOTION_STEPS: usize = 8;
+
+/// Shadow rays per shaded point for area lights that don't set their own and
+/// emissive objects
+const AREA_LIGHT_SAMPLES: u32 = 16;
+
+/// A clear sky
+const DEFAULT_TURBIDITY: f32 = 3.0;
+
 #[derive(Debug, Clone)]
 pub struct SceneConfigLoadError {
     description: String,
@@ -46,6 +57,12 @@ pub struct Scene {
     pub lights: Vec<Box<dyn light::Light>>,
     pub ambient_color: Color,
     pub clear_color: Color,
+    /// Lights the scene from every direction and replaces the clear color
+    pub environment: Option<light::Environment>,
+    /// The range of `objects` built from each named object in the config
+    names: Vec<(String, Range<usize>)>,
+    /// Whether each of `objects` is sampled by one of `lights`
+    emissive: Vec<bool>,
 }
 
 impl Scene {
@@ -60,6 +77,9 @@ impl Scene {
             lights,
             ambient_color,
             clear_color,
+            environment: None,
+            names: vec![],
+            emissive: vec![],
         }
     }
 
@@ -70,13 +90,18 @@ impl Scene {
         fallback_material: Rc<Material>,
     ) -> Result<Scene, SceneConfigLoadError> {
         let mut objects: Vec<Box<dyn Intersectable>> = vec![];
+        let mut names = vec![];
 
         for object in &scene.objects {
+            let first = objects.len();
+
             match *object {
                 Object::Sphere {
                     radius,
                     ref transforms,
+                    ref motion,
                     ref material_name,
+                    ..
                 } => {
                     let material = match material_name {
                         None => fallback_material.clone(),
@@ -90,13 +115,21 @@ impl Scene {
                         }
                     };
                     let mut sphere = Box::new(Sphere::new(Point3::at_origin(), radius, material));
-                    Self::apply_transforms(sphere.as_mut() as &mut dyn Transformable, transforms);
+                    match motion {
+                        Some(keys) => sphere.set_motion(Self::build_motion(keys)?),
+                        None => Self::apply_transforms(
+                            sphere.as_mut() as &mut dyn Transformable,
+                            transforms,
+                        ),
+                    }
                     objects.push(sphere as Box<dyn Intersectable>);
                 }
                 Object::Plane {
                     normal,
                     ref transforms,
+                    ref motion,
                     ref material_name,
+                    ..
                 } => {
                     let material = match material_name {
                         None => fallback_material.clone(),
@@ -115,13 +148,21 @@ impl Scene {
                         Vector3::from(normal),
                         material,
                     ));
-                    Self::apply_transforms(plane.as_mut() as &mut dyn Transformable, transforms);
+                    match motion {
+                        Some(keys) => plane.set_motion(Self::build_motion(keys)?),
+                        None => Self::apply_transforms(
+                            plane.as_mut() as &mut dyn Transformable,
+                            transforms,
+                        ),
+                    }
                     objects.push(plane as Box<dyn Intersectable>);
                 }
                 Object::Mesh {
                     ref path,
                     ref transforms,
+                    ref motion,
                     ref material_name,
+                    ..
                 } => {
                     let material = match material_name {
                         None => fallback_material.clone(),
@@ -144,7 +185,13 @@ impl Scene {
                     };
 
                     for mesh in &mut meshes {
-                        Self::apply_transforms(mesh.as_mut() as &mut dyn Transformable, transforms);
+                        match motion {
+                            Some(keys) => mesh.set_motion(Self::build_motion(keys)?),
+                            None => Self::apply_transforms(
+                                mesh.as_mut() as &mut dyn Transformable,
+                                transforms,
+                            ),
+                        }
                     }
                     let mut intersectables = meshes
                         .into_iter()
@@ -156,7 +203,9 @@ impl Scene {
                 Object::MeshInstance {
                     ref path,
                     ref transforms,
+                    ref motion,
                     ref material_name,
+                    ..
                 } => {
                     let material = match material_name {
                         None => fallback_material.clone(),
@@ -179,7 +228,13 @@ impl Scene {
                     };
 
                     for mesh in &mut meshes {
-                        Self::apply_transforms(mesh.as_mut() as &mut dyn Transformable, transforms);
+                        match motion {
+                            Some(keys) => mesh.set_motion(Self::build_motion(keys)?),
+                            None => Self::apply_transforms(
+                                mesh.as_mut() as &mut dyn Transformable,
+                                transforms,
+                            ),
+                        }
                     }
                     let mut intersectables = meshes
                         .into_iter()
@@ -189,9 +244,13 @@ impl Scene {
                     objects.append(&mut intersectables);
                 }
             }
+
+            if let Some(name) = object.name() {
+                names.push((name.to_owned(), first..objects.len()));
+            }
         }
 
-        let lights: Vec<Box<dyn light::Light>> = scene
+        let mut lights: Vec<Box<dyn light::Light>> = scene
             .lights
             .iter()
             .map(|light| match *light {
@@ -224,15 +283,206 @@ impl Scene {
                     diffuse.unwrap_or(true),
                     specular.unwrap_or(true),
                 )) as Box<dyn light::Light>,
+
+                config::Light::SpotLight {
+                    origin,
+                    direction,
+                    inner_angle,
+                    outer_angle,
+                    falloff_exponent,
+                    color,
+                    intensity,
+                    falloff,
+                    diffuse,
+                    specular,
+                } => Box::new(light::Spot::new(
+                    Point3::from(origin),
+                    Vector3::from(direction),
+                    light::Cone {
+                        inner_angle,
+                        outer_angle,
+                        falloff_exponent: falloff_exponent.unwrap_or(1.0),
+                    },
+                    Color::from(color),
+                    intensity,
+                    falloff.unwrap_or(light::Falloff::InverseSquare),
+                    diffuse.unwrap_or(true),
+                    specular.unwrap_or(true),
+                )) as Box<dyn light::Light>,
+
+                config::Light::AreaLight {
+                    origin,
+                    direction,
+                    up,
+                    shape,
+                    color,
+                    intensity,
+                    samples,
+                    diffuse,
+                    specular,
+                } => Box::new(light::Area::new(
+                    Point3::from(origin),
+                    Vector3::from(direction.unwrap_or([0.0, -1.0, 0.0])),
+                    Vector3::from(up.unwrap_or([0.0, 1.0, 0.0])),
+                    shape,
+                    Color::from(color),
+                    intensity,
+                    samples.unwrap_or(AREA_LIGHT_SAMPLES),
+                    diffuse.unwrap_or(true),
+                    specular.unwrap_or(true),
+                )) as Box<dyn light::Light>,
             })
             .collect();
+        // Every emissive object is a light
+        let mut emissive = vec![false; objects.len()];
+        for (index, object) in objects.iter().enumerate() {
+            let emitters = object.emitters();
+
+            if !emitters.is_empty() {
+                lights.push(Box::new(light::Emissive::new(emitters, AREA_LIGHT_SAMPLES)));
+                emissive[index] = true;
+            }
+        }
 
-        Ok(Self::new(
+        let mut result = Self::new(
             objects,
             lights,
             Color::from(scene.ambient_color),
             Color::from(scene.clear_color),
-        ))
+        );
+        result.names = names;
+        result.emissive = emissive;
+        result.environment = scene
+            .environment
+            .as_ref()
+            .map(|environment| {
+                light::Environment::load(
+                    &mesh_loader.root_path().join(&environment.path),
+                    environment.rotation.unwrap_or(0.0),
+                    environment.intensity.unwrap_or(1.0),
+                    environment.samples.unwrap_or(AREA_LIGHT_SAMPLES),
+                    environment.background.unwrap_or(true),
+                )
+                .map_err(|error| {
+                    SceneConfigLoadError::new(format!(
+                        "Failed to load environment {}: {}",
+                        environment.path, error
+                    ))
+                })
+            })
+            .transpose()?;
+
+        if let Some(sky) = &scene.sky {
+            if result.environment.is_some() {
+                return Err(SceneConfigLoadError::new(
+                    "A scene can't have both an environment and a sky".to_string(),
+                ));
+            }
+            let intensity = sky.intensity.unwrap_or(1.0);
+            let model = light::Sky::new(
+                sky.sun.direction(),
+                sky.turbidity.unwrap_or(DEFAULT_TURBIDITY),
+            );
+
+            if let Some(sun) = model.sun(intensity) {
+                result.lights.push(Box::new(sun));
+            }
+            result.environment = Some(model.environment(
+                intensity,
+                sky.samples.unwrap_or(AREA_LIGHT_SAMPLES),
+                sky.background.unwrap_or(true),
+            ));
+        }
+
+        Ok(result)
+    }
+
+    /// The lights in the scene, including the environment
+    pub fn lights(&self) -> impl Iterator<Item = &dyn light::Light> {
+        self.lights.iter().map(AsRef::as_ref).chain(
+            self.environment
+                .iter()
+                .map(|environment| environment as &dyn light::Light),
+        )
+    }
+
+    /// Radiance along `ray` when it doesn't hit anything, camera rays only see
+    /// the environment when it is a background
+    pub fn background(&self, ray: &Ray, camera_ray: bool) -> Color {
+        match &self.environment {
+            Some(environment) if !camera_ray || environment.is_background() => {
+                environment.radiance(ray.direction)
+            }
+            _ => self.clear_color,
+        }
+    }
+
+    /// World space bounds of the objects called `name`, or of all objects when
+    /// `name` is `None`. Objects without bounds are skipped, `None` when none
+    /// are left.
+    pub fn bounds(&self, name: Option<&str>) -> Option<AABB> {
+        let objects: Vec<&dyn Intersectable> = match name {
+            None => self.objects.iter().map(AsRef::as_ref).collect(),
+            Some(name) => self
+                .names
+                .iter()
+                .filter(|(object_name, _)| object_name == name)
+                .flat_map(|(_, range)| self.objects[range.clone()].iter().map(AsRef::as_ref))
+                .collect(),
+        };
+
+        objects
+            .into_iter()
+            .filter_map(|object| object.bounds())
+            .reduce(|result, bounds| result.union(&bounds))
+    }
+
+    /// Sample the transforms of a moving object densely enough that
+    /// interpolating the matrices in between follows rotations closely
+    fn build_motion(keys: &[config::MotionKey]) -> Result<Motion, SceneConfigLoadError> {
+        let compose = |transforms: &[config::Transform]| {
+            transforms
+                .iter()
+                .fold(Matrix4::identity(), |matrix, transform| {
+                    matrix * transform.to_transform().matrix
+                })
+        };
+        let mut keys = keys.to_vec();
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let Some(last) = keys.last() else {
+            return Err(SceneConfigLoadError::new(String::from(
+                "Motion needs at least one key",
+            )));
+        };
+
+        let mut matrices = vec![(last.time, compose(&last.transforms))];
+        for pair in keys.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            if from.transforms.len() != to.transforms.len() {
+                return Err(SceneConfigLoadError::new(String::from(
+                    "Motion keys should list the same transforms",
+                )));
+            }
+
+            for step in 0..MOTION_STEPS {
+                let t = step as f32 / MOTION_STEPS as f32;
+                let transforms = from
+                    .transforms
+                    .iter()
+                    .zip(&to.transforms)
+                    .map(|(a, b)| a.lerp(b, t))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| {
+                        SceneConfigLoadError::new(String::from(
+                            "Motion keys should list the same transforms in the same order",
+                        ))
+                    })?;
+
+                matrices.push((from.time + (to.time - from.time) * t, compose(&transforms)));
+            }
+        }
+
+        Ok(Motion::new(matrices))
     }
 
     fn apply_transforms(
@@ -249,17 +499,29 @@ impl Scene {
         }
     }
 
-    pub fn intersect(&self, ray: Ray, cull: bool) -> Option<Intersection> {
-        let mut closest_intersection: Option<Intersection> = None;
+    /// Whether the object at `index` in `objects` is sampled as a light
+    pub fn is_light(&self, index: usize) -> bool {
+        self.emissive.get(index).copied().unwrap_or(false)
+    }
+
+    pub fn intersect(&self, ray: Ray, cull: bool) -> Option<Intersection<'_>> {
+        self.intersect_object(ray, cull)
+            .map(|(_, intersection)| intersection)
+    }
+
+    /// The closest intersection along with the index of the intersected
+    /// object in `objects`.
+    pub fn intersect_object(&self, ray: Ray, cull: bool) -> Option<(usize, Intersection<'_>)> {
+        let mut closest_intersection: Option<(usize, Intersection)> = None;
 
-        for shape in &self.objects {
+        for (index, shape) in self.objects.iter().enumerate() {
             if let Some(intersection) = shape.intersect(ray, cull) {
-                if let Some(closest) = closest_intersection {
+                if let Some((_, closest)) = closest_intersection {
                     if intersection.t < closest.t {
-                        closest_intersection = Some(intersection)
+                        closest_intersection = Some((index, intersection))
                     }
                 } else {
-                    closest_intersection = Some(intersection)
+                    closest_intersection = Some((index, intersection))
                 }
             }
         }
@@ -267,7 +529,12 @@ impl Scene {
         closest_intersection
     }
 
-    pub fn first_intersection(&self, ray: Ray, cull: bool, distance: f32) -> Option<Intersection> {
+    pub fn first_intersection(
+        &self,
+        ray: Ray,
+        cull: bool,
+        distance: f32,
+    ) -> Option<Intersection<'_>> {
         for object in &self.objects {
             if let Some(hit) = object.intersect(ray, cull) {
                 if hit.t < distance {
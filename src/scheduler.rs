@@ -0,0 +1,295 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use crate::config;
+
+/// The order in which tiles are handed out to the render threads.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TileOrder {
+    /// Left to right, top to bottom
+    Scanline,
+    /// Outwards from the center of the image, where the subject usually is
+    Spiral,
+    /// Along a Hilbert curve, consecutive tiles are mostly adjacent which
+    /// keeps the working set of the threads coherent. The curve covers the
+    /// next power of two square, it jumps where it leaves the image.
+    Hilbert,
+}
+
+/// A rectangle of pixels that is rendered as one unit of work.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Reported after each tile has been rendered and merged into the
+/// framebuffer.
+#[derive(Debug, Copy, Clone)]
+pub struct TileProgress {
+    pub tile: Tile,
+    pub completed_tiles: usize,
+    pub total_tiles: usize,
+}
+
+impl TileProgress {
+    /// Fraction of the frame that is done, in [0, 1]
+    pub fn fraction(&self) -> f32 {
+        self.completed_tiles as f32 / self.total_tiles.max(1) as f32
+    }
+}
+
+/// A cheap, cloneable handle used to stop a render from another thread.
+/// Tiles that are in flight when the token is cancelled are finished, no new
+/// tiles are started.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Schedule {
+    pub tile_size: u32,
+    pub order: TileOrder,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            tile_size: 32,
+            order: TileOrder::Scanline,
+        }
+    }
+}
+
+impl Schedule {
+    /// Split a `width` × `height` image into tiles, sorted in the order they
+    /// should be rendered.
+    pub fn tiles(&self, width: u32, height: u32) -> Vec<Tile> {
+        let size = self.tile_size.max(1);
+        let (columns, rows) = (width.div_ceil(size), height.div_ceil(size));
+        let tile = |column: u32, row: u32| Tile {
+            x: column * size,
+            y: row * size,
+            width: size.min(width - column * size),
+            height: size.min(height - row * size),
+        };
+
+        let coordinates = match self.order {
+            TileOrder::Scanline => (0..rows)
+                .flat_map(|row| (0..columns).map(move |column| (column, row)))
+                .collect(),
+            TileOrder::Spiral => spiral(columns, rows),
+            TileOrder::Hilbert => {
+                let mut coordinates: Vec<_> = (0..rows)
+                    .flat_map(|row| (0..columns).map(move |column| (column, row)))
+                    .collect();
+                let order = columns.max(rows).next_power_of_two();
+                coordinates.sort_by_key(|&(column, row)| hilbert_index(order, column, row));
+
+                coordinates
+            }
+        };
+
+        coordinates
+            .into_iter()
+            .map(|(column, row)| tile(column, row))
+            .collect()
+    }
+}
+
+/// Run `work` for each tile on the rayon pool. Tiles are started strictly in
+/// order, but finish in whatever order the threads get through them.
+pub(crate) fn for_each_tile<F>(tiles: &[Tile], cancellation: &CancellationToken, work: F)
+where
+    F: Fn(Tile) + Sync,
+{
+    let next = AtomicUsize::new(0);
+
+    (0..rayon::current_num_threads())
+        .into_par_iter()
+        .for_each(|_| loop {
+            if cancellation.is_cancelled() {
+                break;
+            }
+
+            let index = next.fetch_add(1, Ordering::SeqCst);
+            match tiles.get(index) {
+                Some(tile) => work(*tile),
+                None => break,
+            }
+        });
+}
+
+/// Walk outwards from the center in a square spiral, skipping positions
+/// outside of the grid.
+fn spiral(columns: u32, rows: u32) -> Vec<(u32, u32)> {
+    let total = (columns * rows) as usize;
+    let mut result = Vec::with_capacity(total);
+    let (mut x, mut y) = ((columns as i64 - 1) / 2, (rows as i64 - 1) / 2);
+    let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut leg_length = 1;
+    let mut direction = 0;
+
+    let visit = |x: i64, y: i64, result: &mut Vec<(u32, u32)>| {
+        if x >= 0 && y >= 0 && x < i64::from(columns) && y < i64::from(rows) {
+            result.push((x as u32, y as u32));
+        }
+    };
+    visit(x, y, &mut result);
+
+    while result.len() < total {
+        // Each leg length is walked twice before growing
+        for _ in 0..2 {
+            let (dx, dy) = directions[direction % 4];
+            for _ in 0..leg_length {
+                x += dx;
+                y += dy;
+                visit(x, y, &mut result);
+            }
+            direction += 1;
+        }
+        leg_length += 1;
+    }
+
+    result
+}
+
+/// Distance along the Hilbert curve covering an `order` × `order` grid, where
+/// `order` is a power of two.
+fn hilbert_index(order: u32, x: u32, y: u32) -> u64 {
+    let (mut x, mut y) = (u64::from(x), u64::from(y));
+    let mut distance = 0;
+    let mut s = u64::from(order) / 2;
+
+    while s > 0 {
+        let rx = u64::from(x & s > 0);
+        let ry = u64::from(y & s > 0);
+        distance += s * s * ((3 * rx) ^ ry);
+
+        // Rotate the quadrant so that the curve stays continuous
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - (x & (s - 1));
+                y = s - 1 - (y & (s - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        s /= 2;
+    }
+
+    distance
+}
+
+impl From<&config::Tiles> for Schedule {
+    fn from(config: &config::Tiles) -> Self {
+        let defaults = Self::default();
+
+        Self {
+            tile_size: config.size.unwrap_or(defaults.tile_size),
+            order: config.order.unwrap_or(defaults.order),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn all_orders() -> [TileOrder; 3] {
+        [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert]
+    }
+
+    #[test]
+    fn test_tiles_cover_image_exactly_once() {
+        let (width, height) = (150, 70);
+
+        for order in &all_orders() {
+            let schedule = Schedule {
+                tile_size: 16,
+                order: *order,
+            };
+            let mut coverage = vec![0; (width * height) as usize];
+
+            for tile in schedule.tiles(width, height) {
+                for y in tile.y..tile.y + tile.height {
+                    for x in tile.x..tile.x + tile.width {
+                        coverage[(y * width + x) as usize] += 1;
+                    }
+                }
+            }
+
+            assert!(coverage.iter().all(|&count| count == 1), "{:?}", order);
+        }
+    }
+
+    #[test]
+    fn test_spiral_starts_in_the_center() {
+        let schedule = Schedule {
+            tile_size: 10,
+            order: TileOrder::Spiral,
+        };
+        let tiles = schedule.tiles(50, 30);
+
+        assert_eq!((tiles[0].x, tiles[0].y), (20, 10));
+        assert_eq!((tiles[1].x, tiles[1].y), (30, 10));
+    }
+
+    #[test]
+    fn test_hilbert_tiles_are_adjacent() {
+        let schedule = Schedule {
+            tile_size: 8,
+            order: TileOrder::Hilbert,
+        };
+        let tiles = schedule.tiles(64, 64);
+
+        for pair in tiles.windows(2) {
+            let dx = (pair[0].x as i64 - pair[1].x as i64).abs();
+            let dy = (pair[0].y as i64 - pair[1].y as i64).abs();
+
+            assert_eq!(dx + dy, 8);
+        }
+    }
+
+    #[test]
+    fn test_cancellation_stops_handing_out_tiles() {
+        let schedule = Schedule::default();
+        let tiles = schedule.tiles(1024, 1024);
+        let token = CancellationToken::new();
+        let rendered = Mutex::new(0);
+
+        for_each_tile(&tiles, &token, |_| {
+            let mut rendered = rendered.lock().unwrap();
+            *rendered += 1;
+
+            if *rendered == 10 {
+                token.cancel();
+            }
+        });
+
+        let rendered = *rendered.lock().unwrap();
+        assert!(rendered >= 10);
+        assert!(rendered < 10 + rayon::current_num_threads());
+    }
+}
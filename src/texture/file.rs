@@ -6,6 +6,7 @@ use image;
 use image::GenericImageView;
 
 use crate::color::Color;
+use crate::tone_mapping::srgb_to_linear;
 
 use super::{Texture, TextureCoord};
 
@@ -41,12 +42,27 @@ impl Error for FileError {
 pub struct File {
     image: image::DynamicImage,
     path: PathBuf,
+    /// Whether the pixels are sRGB encoded colors rather than linear data
+    srgb: bool,
 }
 
 impl File {
     pub fn new(path: PathBuf) -> Result<Self, FileError> {
         let image = image::open(&path)?;
-        Ok(File { path, image })
+        Ok(File {
+            path,
+            image,
+            srgb: true,
+        })
+    }
+
+    /// A texture holding data instead of colors, like a mask, which is used
+    /// as is
+    pub fn new_linear(path: PathBuf) -> Result<Self, FileError> {
+        Ok(File {
+            srgb: false,
+            ..Self::new(path)?
+        })
     }
 }
 
@@ -63,8 +79,14 @@ impl Texture for File {
         let y = (f64::from(boundex_v) * ((height - 1) as f64)).round() as u32;
 
         let pixel = self.image.get_pixel(x, y);
+        let color = Color::new_u8(pixel[0], pixel[1], pixel[2]);
 
-        Color::new(pixel[0], pixel[1], pixel[2])
+        // Color images are sRGB encoded, shading happens in linear space
+        if self.srgb {
+            color.map(srgb_to_linear)
+        } else {
+            color
+        }
     }
 }
 
@@ -73,3 +95,25 @@ impl fmt::Debug for File {
         write!(f, "File {{ path: {:?} }}", self.path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_lookup() {
+        let path = std::env::temp_dir().join("rusttracer_test_linear_lookup.png");
+        image::GrayImage::from_pixel(1, 1, image::Luma([128]))
+            .save(&path)
+            .unwrap();
+        let uv = TextureCoord::new(0.0, 0.0);
+
+        let color = File::new(path.clone()).unwrap().lookup(uv);
+        let mask = File::new_linear(path.clone()).unwrap().lookup(uv);
+        std::fs::remove_file(&path).unwrap();
+
+        // Masks skip the sRGB decode of colors
+        assert!((mask.r() - 128.0 / 255.0).abs() < 1e-5);
+        assert!(color.r() < 0.25);
+    }
+}
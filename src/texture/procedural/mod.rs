@@ -61,13 +61,13 @@ mod test {
 
     #[test]
     fn test_lookup() {
-        let t = Procedural::new(|uv| Color::new_f32(uv.x, uv.y, 0.0));
+        let t = Procedural::new(|uv| Color::new(uv.x, uv.y, 0.0));
         let c1 = TextureCoord::new(0.0, 1.0);
         let c2 = TextureCoord::new(1.0, 0.0);
         let c3 = TextureCoord::new(0.0, 0.0);
 
-        assert_eq!(t.lookup(c1), Color::new(0, 255, 0));
-        assert_eq!(t.lookup(c2), Color::new(255, 0, 0));
-        assert_eq!(t.lookup(c3), Color::new(0, 0, 0));
+        assert_eq!(t.lookup(c1), Color::green());
+        assert_eq!(t.lookup(c2), Color::red());
+        assert_eq!(t.lookup(c3), Color::black());
     }
 }
@@ -25,7 +25,7 @@ mod test {
 
     #[test]
     fn test_lookup() {
-        let c = Color::new(255, 127, 53);
+        let c = Color::new_u8(255, 127, 53);
         let t = Solid::new(c);
         let c1 = TextureCoord::new(0.0, 1.0);
         let c2 = TextureCoord::new(1.0, 0.0);
@@ -0,0 +1,257 @@
+use serde::Deserialize;
+
+use crate::camera::DEFAULT_WHITE_BALANCE;
+use crate::color::Color;
+use crate::config;
+
+/// White point used by the Uncharted 2 filmic curve, see
+/// http://filmicworlds.com/blog/filmic-tonemapping-operators/
+const HABLE_WHITE_POINT: f32 = 11.2;
+
+/// Maps unbounded scene referred radiance into the [0, 1] display range.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
+pub enum Operator {
+    /// Clamp each channel to [0, 1]
+    Clamp,
+    /// Reinhard et al. 2002, `L / (1 + L)` applied to luminance.
+    Reinhard,
+    /// Reinhard with a white point, luminance at or above `white_point` maps
+    /// to 1.0.
+    ExtendedReinhard { white_point: f32 },
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve.
+    Aces,
+    /// John Hable's filmic curve from Uncharted 2.
+    Hable,
+}
+
+/// Encodes linear values for display.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
+pub enum TransferFunction {
+    /// Write linear values without any encoding
+    Linear,
+    /// The piecewise sRGB transfer function (IEC 61966-2-1)
+    Srgb,
+    /// A pure power law with the given gamma, e.g. 2.2
+    Gamma(f32),
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ToneMapping {
+    /// Exposure in stops (EV), the scene is scaled by `2^exposure` before tone mapping.
+    pub exposure: f32,
+    /// Per channel gains that make the light of the camera's white balance
+    /// temperature appear white
+    pub white_balance: Color,
+    pub operator: Operator,
+    pub transfer_function: TransferFunction,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self::new(0.0, Operator::Clamp, TransferFunction::Srgb)
+    }
+}
+
+impl ToneMapping {
+    pub fn new(exposure: f32, operator: Operator, transfer_function: TransferFunction) -> Self {
+        Self {
+            exposure,
+            white_balance: Color::white(),
+            operator,
+            transfer_function,
+        }
+    }
+
+    /// Set the white balance so that a black body at `temperature` Kelvin
+    /// appears like one at D65 does without correction.
+    pub fn with_white_balance(mut self, temperature: f32) -> Self {
+        let reference = Color::from_temperature(DEFAULT_WHITE_BALANCE);
+        let source = Color::from_temperature(temperature).map(|channel| channel.max(1e-4));
+
+        self.white_balance = Color::new(
+            reference.r() / source.r(),
+            reference.g() / source.g(),
+            reference.b() / source.b(),
+        );
+        self
+    }
+
+    /// Turn linear scene radiance into a display encoded color in [0, 1].
+    pub fn apply(&self, color: Color) -> Color {
+        let exposed = (color * self.white_balance * 2.0_f32.powf(self.exposure))
+            .map(|channel| channel.max(0.0));
+        let mapped = self
+            .operator
+            .apply(exposed)
+            .map(|channel| channel.clamp(0.0, 1.0));
+
+        self.transfer_function.encode(mapped)
+    }
+}
+
+impl Operator {
+    pub fn apply(&self, color: Color) -> Color {
+        match *self {
+            Operator::Clamp => color,
+            Operator::Reinhard => Self::scale_luminance(color, |l| l / (1.0 + l)),
+            Operator::ExtendedReinhard { white_point } => {
+                let white_squared = white_point * white_point;
+
+                Self::scale_luminance(color, |l| l * (1.0 + l / white_squared) / (1.0 + l))
+            }
+            Operator::Aces => {
+                color.map(|x| (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14))
+            }
+            Operator::Hable => {
+                let white_scale = 1.0 / Self::hable_partial(HABLE_WHITE_POINT);
+
+                // The exposure bias of 2.0 is part of the original formulation
+                color.map(|x| Self::hable_partial(x * 2.0) * white_scale)
+            }
+        }
+    }
+
+    fn scale_luminance<F>(color: Color, curve: F) -> Color
+    where
+        F: Fn(f32) -> f32,
+    {
+        let luminance = color.luminance();
+        if luminance <= 0.0 {
+            return Color::black();
+        }
+
+        color * (curve(luminance) / luminance)
+    }
+
+    fn hable_partial(x: f32) -> f32 {
+        const A: f32 = 0.15;
+        const B: f32 = 0.50;
+        const C: f32 = 0.10;
+        const D: f32 = 0.20;
+        const E: f32 = 0.02;
+        const F: f32 = 0.30;
+
+        ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+    }
+}
+
+impl TransferFunction {
+    pub fn encode(&self, color: Color) -> Color {
+        match *self {
+            TransferFunction::Linear => color,
+            TransferFunction::Srgb => color.map(linear_to_srgb),
+            TransferFunction::Gamma(gamma) => color.map(|channel| channel.powf(1.0 / gamma)),
+        }
+    }
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl From<&config::ToneMapping> for ToneMapping {
+    fn from(config: &config::ToneMapping) -> Self {
+        let defaults = Self::default();
+
+        Self::new(
+            config.exposure.unwrap_or(defaults.exposure),
+            config.operator.unwrap_or(defaults.operator),
+            config
+                .transfer_function
+                .unwrap_or(defaults.transfer_function),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    #[test]
+    fn test_exposure() {
+        let tone_mapping = ToneMapping::new(1.0, Operator::Clamp, TransferFunction::Linear);
+
+        assert_eq!(
+            tone_mapping.apply(Color::new(0.25, 0.1, 2.0)),
+            Color::new(0.5, 0.2, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_white_balance() {
+        let neutral = ToneMapping::new(0.0, Operator::Clamp, TransferFunction::Linear)
+            .with_white_balance(DEFAULT_WHITE_BALANCE);
+        assert_eq!(neutral.white_balance, Color::white());
+
+        let tungsten = ToneMapping::new(0.0, Operator::Clamp, TransferFunction::Linear)
+            .with_white_balance(3200.0);
+        let balanced = tungsten.apply(Color::from_temperature(3200.0) * 0.5);
+        let daylight = Color::from_temperature(DEFAULT_WHITE_BALANCE) * 0.5;
+
+        assert_eq_within_bound!(balanced.r(), daylight.r(), EPSILON);
+        assert_eq_within_bound!(balanced.g(), daylight.g(), EPSILON);
+        assert_eq_within_bound!(balanced.b(), daylight.b(), EPSILON);
+    }
+
+    #[test]
+    fn test_reinhard() {
+        let mapped = Operator::Reinhard.apply(Color::white());
+
+        assert_eq_within_bound!(mapped.r(), 0.5, EPSILON);
+        assert_eq_within_bound!(mapped.g(), 0.5, EPSILON);
+        assert_eq_within_bound!(mapped.b(), 0.5, EPSILON);
+    }
+
+    #[test]
+    fn test_extended_reinhard_white_point() {
+        let operator = Operator::ExtendedReinhard { white_point: 4.0 };
+        let mapped = operator.apply(Color::white() * 4.0);
+
+        assert_eq_within_bound!(mapped.luminance(), 1.0, EPSILON);
+    }
+
+    #[test]
+    fn test_filmic_curves_are_monotonic_and_bounded() {
+        for operator in &[Operator::Aces, Operator::Hable] {
+            let tone_mapping = ToneMapping::new(0.0, *operator, TransferFunction::Linear);
+            let mut previous = 0.0;
+
+            for i in 1..200 {
+                let value = tone_mapping.apply(Color::white() * (i as f32 * 0.1)).r();
+
+                assert!(value >= previous, "{:?} is not monotonic", operator);
+                assert!(value <= 1.0, "{:?} is out of range", operator);
+                previous = value;
+            }
+
+            assert!(previous > 0.95, "{:?} never reaches white", operator);
+        }
+    }
+
+    #[test]
+    fn test_srgb_round_trip() {
+        for i in 0..=100 {
+            let value = i as f32 / 100.0;
+
+            assert_eq_within_bound!(srgb_to_linear(linear_to_srgb(value)), value, EPSILON);
+        }
+
+        assert_eq_within_bound!(linear_to_srgb(0.0), 0.0, EPSILON);
+        assert_eq_within_bound!(linear_to_srgb(1.0), 1.0, EPSILON);
+        assert_eq_within_bound!(linear_to_srgb(0.214_041), 0.5, EPSILON);
+    }
+}